@@ -0,0 +1,336 @@
+//! `#[derive(ToNbt, FromNbt)]` for structs with named fields, generating code that writes to an
+//! `nbt::writer::NbtSink` and reads from an `nbt::reader::NbtSource` without going through
+//! `nbt::Value`.
+//!
+//! Field attributes, all under `#[nbt(...)]`:
+//!
+//! - `rename = "Key"` stores the field under another key.
+//! - `default` or `default = "path::to::fn"` fills in a missing key instead of failing.
+//! - `flatten` writes the entries of a nested struct into this compound.
+//! - `byte_array`, `int_array` or `long_array` writes a `Vec` (or `Option<Vec>`) as the
+//!   matching array tag; `list`, the default, writes it as a list.
+//!
+//! `Option` fields are left out when `None` and read as `None` when missing.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, PathArguments, Type};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Plain,
+    Flatten,
+    Array(&'static str),
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    key: String,
+    mode: Mode,
+    default: Option<Option<syn::Path>>,
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first()? {
+        GenericArgument::Type(ty) if args.args.len() == 1 => Some(ty),
+        _ => None,
+    }
+}
+
+fn parse_fields(input: &DeriveInput) -> syn::Result<Vec<Field>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(&input.ident, "nbt derives only support structs"));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(syn::Error::new_spanned(&input.ident, "nbt derives only support structs with named fields"));
+    };
+
+    let mut fields = Vec::new();
+    for field in &named.named {
+        let ident = field.ident.clone().expect("named field");
+        let mut key = ident.to_string();
+        // `list` is the default, but still conflicts with the other modes when given
+        let mut mode = None;
+        let mut default = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("nbt")) {
+            attr.parse_nested_meta(|meta| {
+                let mut set_mode = |m: Mode| {
+                    if mode.is_some() {
+                        return Err(meta.error("only one of flatten, list, byte_array, int_array and long_array is allowed"));
+                    }
+                    mode = Some(m);
+                    Ok(())
+                };
+                if meta.path.is_ident("rename") {
+                    key = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("default") {
+                    default = Some(match meta.input.peek(syn::Token![=]) {
+                        true => Some(meta.value()?.parse::<LitStr>()?.parse::<syn::Path>()?),
+                        false => None,
+                    });
+                } else if meta.path.is_ident("flatten") {
+                    set_mode(Mode::Flatten)?;
+                } else if meta.path.is_ident("list") {
+                    set_mode(Mode::Plain)?;
+                } else if meta.path.is_ident("byte_array") {
+                    set_mode(Mode::Array("ByteArray"))?;
+                } else if meta.path.is_ident("int_array") {
+                    set_mode(Mode::Array("IntArray"))?;
+                } else if meta.path.is_ident("long_array") {
+                    set_mode(Mode::Array("LongArray"))?;
+                } else {
+                    return Err(meta.error("unknown nbt attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        let mode = mode.unwrap_or(Mode::Plain);
+        if mode == Mode::Flatten && default.is_some() {
+            return Err(syn::Error::new_spanned(&ident, "flattened fields cannot have a default"));
+        }
+        fields.push(Field { ident, ty: field.ty.clone(), key, mode, default });
+    }
+    Ok(fields)
+}
+
+fn add_bounds(input: &DeriveInput, bounds: impl Iterator<Item = TokenStream2>) -> syn::Generics {
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for bound in bounds {
+            where_clause.predicates.push(syn::parse2(bound).expect("valid where predicate"));
+        }
+    }
+    generics
+}
+
+#[proc_macro_derive(ToNbt, attributes(nbt))]
+pub fn derive_to_nbt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match to_nbt(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn to_nbt(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input)?;
+    let name = &input.ident;
+
+    let writes = fields.iter().map(|f| {
+        let Field { ident, key, .. } = f;
+        match f.mode {
+            Mode::Plain => quote! { ::nbt::codec::write_field(w, #key, &self.#ident)?; },
+            Mode::Flatten => quote! { ::nbt::codec::ToNbtCompound::write_entries(&self.#ident, w)?; },
+            Mode::Array(wrapper) => {
+                let wrapper = format_ident!("{}", wrapper);
+                if option_inner(&f.ty).is_some() {
+                    quote! {
+                        if let ::core::option::Option::Some(v) = &self.#ident {
+                            ::nbt::codec::write_field(w, #key, &::nbt::codec::#wrapper(v))?;
+                        }
+                    }
+                } else {
+                    quote! { ::nbt::codec::write_field(w, #key, &::nbt::codec::#wrapper(&self.#ident))?; }
+                }
+            }
+        }
+    });
+    let generics = add_bounds(
+        input,
+        fields.iter().filter(|f| !matches!(f.mode, Mode::Array(_))).map(|f| {
+            let ty = &f.ty;
+            match f.mode {
+                Mode::Flatten => quote! { #ty: ::nbt::codec::ToNbtCompound },
+                _ => quote! { #ty: ::nbt::codec::ToNbt },
+            }
+        }),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::nbt::codec::ToNbtCompound for #name #ty_generics #where_clause {
+            fn write_entries<W: ::nbt::writer::NbtSink>(&self, w: &mut W) -> ::nbt::Result<()> {
+                #(#writes)*
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        impl #impl_generics ::nbt::codec::ToNbt for #name #ty_generics #where_clause {
+            const TAG: u8 = 10;
+
+            fn write_payload<W: ::nbt::writer::NbtSink>(&self, w: &mut W) -> ::nbt::Result<()> {
+                ::nbt::codec::ToNbtCompound::write_entries(self, w)?;
+                ::nbt::writer::NbtSink::write_u8(w, 0)
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(FromNbt, attributes(nbt))]
+pub fn derive_from_nbt(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match from_nbt(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn from_nbt(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input)?;
+    let name = &input.ident;
+    let vis = &input.vis;
+    let builder = format_ident!("__{}NbtBuilder", name);
+
+    // what the builder holds for each field while the compound is read
+    let slots = fields.iter().map(|f| {
+        let Field { ident, ty, .. } = f;
+        match f.mode {
+            Mode::Flatten => quote! { #ident: <#ty as ::nbt::codec::FromNbtCompound>::Builder },
+            _ => quote! { #ident: ::core::option::Option<#ty> },
+        }
+    });
+    let empty_slots = fields.iter().map(|f| {
+        let ident = &f.ident;
+        match f.mode {
+            Mode::Flatten => quote! { #ident: ::core::default::Default::default() },
+            _ => quote! { #ident: ::core::option::Option::None },
+        }
+    });
+
+    let arms = fields.iter().filter(|f| f.mode != Mode::Flatten).map(|f| {
+        let Field { ident, ty, key, .. } = f;
+        let read = match (f.mode, option_inner(ty)) {
+            (Mode::Array(wrapper), Some(inner)) => {
+                let wrapper = format_ident!("{}", wrapper);
+                quote! {
+                    ::core::option::Option::Some(
+                        <::nbt::codec::#wrapper<#inner> as ::nbt::codec::FromNbt>::read_payload(r, tag)?.0,
+                    )
+                }
+            }
+            (Mode::Array(wrapper), None) => {
+                let wrapper = format_ident!("{}", wrapper);
+                quote! { <::nbt::codec::#wrapper<#ty> as ::nbt::codec::FromNbt>::read_payload(r, tag)?.0 }
+            }
+            _ => quote! { <#ty as ::nbt::codec::FromNbt>::read_payload(r, tag)? },
+        };
+        quote! {
+            #key => {
+                builder.#ident = ::core::option::Option::Some(#read);
+                ::core::result::Result::Ok(true)
+            }
+        }
+    });
+    let flattened = fields.iter().filter(|f| f.mode == Mode::Flatten).map(|f| {
+        let Field { ident, ty, .. } = f;
+        quote! {
+            if <#ty as ::nbt::codec::FromNbtCompound>::read_entry(&mut builder.#ident, key, tag, r)? {
+                return ::core::result::Result::Ok(true);
+            }
+        }
+    });
+
+    let values = fields.iter().map(|f| {
+        let Field { ident, ty, key, .. } = f;
+        let missing = quote! { ::nbt::Error::MissingField(::std::string::String::from(#key)) };
+        let value = match (&f.default, f.mode) {
+            (_, Mode::Flatten) => quote! { <#ty as ::nbt::codec::FromNbtCompound>::build(builder.#ident)? },
+            (Some(Some(path)), _) => quote! { builder.#ident.unwrap_or_else(#path) },
+            (Some(None), _) => quote! { builder.#ident.unwrap_or_default() },
+            (None, Mode::Array(_)) if option_inner(ty).is_some() => quote! { builder.#ident.unwrap_or(::core::option::Option::None) },
+            (None, Mode::Array(_)) => quote! { builder.#ident.ok_or_else(|| #missing)? },
+            (None, _) => quote! {
+                match builder.#ident {
+                    ::core::option::Option::Some(v) => v,
+                    ::core::option::Option::None => <#ty as ::nbt::codec::FromNbt>::missing().ok_or_else(|| #missing)?,
+                }
+            },
+        };
+        quote! { #ident: #value }
+    });
+
+    let generics = add_bounds(
+        input,
+        fields.iter().filter(|f| !matches!(f.mode, Mode::Array(_))).map(|f| {
+            let ty = &f.ty;
+            match f.mode {
+                Mode::Flatten => quote! { #ty: ::nbt::codec::FromNbtCompound },
+                _ => quote! { #ty: ::nbt::codec::FromNbt },
+            }
+        }),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis struct #builder #impl_generics #where_clause {
+            #(#slots,)*
+        }
+
+        impl #impl_generics ::core::default::Default for #builder #ty_generics #where_clause {
+            fn default() -> Self {
+                #builder { #(#empty_slots,)* }
+            }
+        }
+
+        impl #impl_generics ::nbt::codec::FromNbtCompound for #name #ty_generics #where_clause {
+            type Builder = #builder #ty_generics;
+
+            #[allow(clippy::match_single_binding)]
+            fn read_entry<R: ::nbt::reader::NbtSource>(
+                builder: &mut Self::Builder,
+                key: &str,
+                tag: u8,
+                r: &mut R,
+            ) -> ::nbt::Result<bool> {
+                match key {
+                    #(#arms)*
+                    _ => {
+                        #(#flattened)*
+                        ::core::result::Result::Ok(false)
+                    }
+                }
+            }
+
+            fn build(builder: Self::Builder) -> ::nbt::Result<Self> {
+                ::core::result::Result::Ok(#name { #(#values,)* })
+            }
+        }
+
+        impl #impl_generics ::nbt::codec::FromNbt for #name #ty_generics #where_clause {
+            const TAG: u8 = 10;
+
+            fn read_payload<R: ::nbt::reader::NbtSource>(r: &mut R, tag: u8) -> ::nbt::Result<Self> {
+                ::nbt::codec::read_compound::<Self, R>(r, tag)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        parse_fields(&input).err().expect("conflicting attributes are rejected").to_string()
+    }
+
+    #[test]
+    fn test_conflicting_modes() {
+        let message = "only one of flatten, list, byte_array, int_array and long_array is allowed";
+        assert_eq!(error(syn::parse_quote! { struct S { #[nbt(list, int_array)] a: Vec<i32> } }), message);
+        assert_eq!(error(syn::parse_quote! { struct S { #[nbt(long_array)] #[nbt(list)] a: Vec<i64> } }), message);
+        assert_eq!(error(syn::parse_quote! { struct S { #[nbt(list, flatten)] a: Inner } }), message);
+        assert!(parse_fields(&syn::parse_quote! { struct S { #[nbt(list)] a: Vec<i32>, #[nbt(int_array)] b: Vec<i32> } }).is_ok());
+    }
+}
@@ -0,0 +1,65 @@
+//! Decoding and encoding of array-heavy NBT: `bigtest.nbt`, the synthetic chunk from `corpus`
+//! whose block states are long arrays, and a bare long array against an element-by-element
+//! baseline.
+
+mod corpus;
+
+use std::fs;
+use std::hint::black_box;
+use std::io::Read;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use flate2::read::GzDecoder;
+use nbt::reader::{NbtReader, NbtSource};
+use nbt::writer::NbtWriter;
+use nbt::{Nbt, NbtReadTrait, NbtWriteTrait, Value};
+
+fn bigtest() -> Vec<u8> {
+    let mut data = Vec::new();
+    GzDecoder::new(&fs::read("src/test/bigtest.nbt").unwrap()[..]).read_to_end(&mut data).unwrap();
+    data
+}
+
+fn encode(value: &Value) -> Vec<u8> {
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, value).unwrap();
+    w.data
+}
+
+fn documents(c: &mut Criterion) {
+    for (name, data) in [("bigtest", bigtest()), ("chunk", encode(&corpus::chunk()))] {
+        let value = Nbt::from_reader(&mut NbtReader::new(&data)).unwrap();
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_function("read", |b| b.iter(|| Nbt::from_reader(&mut NbtReader::new(black_box(&data))).unwrap()));
+        group.bench_function("write", |b| b.iter(|| encode(black_box(&value))));
+        group.finish();
+    }
+}
+
+fn long_array(c: &mut Criterion) {
+    let longs: Vec<i64> = (0..4096).map(|i| i * 0x0101_0101_0101).collect();
+    let mut w = NbtWriter::new();
+    Nbt::write_long_array(&mut w, &longs).unwrap();
+    let data = w.data;
+
+    let mut group = c.benchmark_group("long_array");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("read_bulk", |b| {
+        b.iter(|| Nbt::read_long_array(&mut NbtReader::new(black_box(&data))).unwrap())
+    });
+    group.bench_function("read_per_element", |b| {
+        b.iter(|| {
+            let mut r = NbtReader::new(black_box(&data));
+            let len = r.read_len().unwrap();
+            (0..len).map(|_| r.read_i64().unwrap()).collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("write_bulk", |b| {
+        b.iter_batched_ref(NbtWriter::new, |w| Nbt::write_long_array(w, black_box(&longs)).unwrap(), BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, documents, long_array);
+criterion_main!(benches);
@@ -0,0 +1,395 @@
+//! Synthetic documents shaped like the files vanilla 1.20 writes, generated from a fixed seed so
+//! that every run measures the same bytes.
+
+// each bench uses its own subset
+#![allow(dead_code)]
+
+use nbt::{Compound, Value};
+
+pub const DATA_VERSION: i32 = 3465;
+
+/// Xorshift, so that the corpora need no dependency and never change between runs.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new() -> Rng {
+        Rng(0x2545f4914f6cdd1d)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+pub fn compound<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Compound(entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect::<Compound>())
+}
+
+pub fn string(s: impl Into<String>) -> Value {
+    Value::String(s.into())
+}
+
+fn doubles(values: &[f64]) -> Value {
+    Value::List(values.iter().map(|&v| Value::Double(v)).collect())
+}
+
+fn ints(values: &[i32]) -> Value {
+    Value::List(values.iter().map(|&v| Value::Int(v)).collect())
+}
+
+const ITEMS: [&str; 12] = [
+    "stone", "cobblestone", "oak_planks", "torch", "diamond_sword", "iron_pickaxe", "bread", "arrow",
+    "redstone", "bucket", "shield", "cooked_beef",
+];
+
+fn item(rng: &mut Rng, slot: i8) -> Value {
+    let id = ITEMS[rng.below(ITEMS.len() as u64) as usize];
+    let mut item = compound([
+        ("Slot", Value::Byte(slot)),
+        ("id", string(format!("minecraft:{}", id))),
+        ("Count", Value::Byte(1 + rng.below(64) as i8)),
+    ]);
+    if id.ends_with("sword") || id.ends_with("pickaxe") || id == "shield" {
+        let enchantments = (0..1 + rng.below(3))
+            .map(|i| compound([("id", string(format!("minecraft:enchantment_{}", i))), ("lvl", Value::Short(1 + i as i16))]))
+            .collect();
+        let tag = compound([
+            ("Damage", Value::Int(rng.below(250) as i32)),
+            ("RepairCost", Value::Int(3)),
+            ("Enchantments", Value::List(enchantments)),
+            (
+                "display",
+                compound([
+                    ("Name", string(format!("{{\"text\":\"Slot {}\",\"italic\":false}}", slot))),
+                    ("Lore", Value::List(vec![string("{\"text\":\"Found in a dungeon\"}")])),
+                ]),
+            ),
+        ]);
+        item.as_compound_mut().unwrap().insert("tag".to_string(), tag);
+    }
+    item
+}
+
+/// A player `.dat` with full inventory, ender chest and an unlocked recipe book.
+pub fn player() -> Value {
+    let mut rng = Rng::new();
+    let attribute = |name: &str, base: f64| compound([("Name", string(name)), ("Base", Value::Double(base))]);
+    let recipes: Vec<Value> = (0..700).map(|i| string(format!("minecraft:recipe_{}", i))).collect();
+    compound([
+        ("DataVersion", Value::Int(DATA_VERSION)),
+        ("Pos", doubles(&[-152.5, 64.0, 301.25])),
+        ("Motion", doubles(&[0.0, -0.0784000015258789, 0.0])),
+        ("Rotation", Value::List(vec![Value::Float(91.5), Value::Float(12.0)])),
+        ("FallDistance", Value::Float(0.0)),
+        ("Fire", Value::Short(-20)),
+        ("Air", Value::Short(300)),
+        ("OnGround", Value::Byte(1)),
+        ("Invulnerable", Value::Byte(0)),
+        ("PortalCooldown", Value::Int(0)),
+        ("UUID", Value::IntArray(vec![-1298961296, 1189414636, -1527383917, 1683765433])),
+        ("Health", Value::Float(20.0)),
+        ("HurtTime", Value::Short(0)),
+        ("DeathTime", Value::Short(0)),
+        ("HurtByTimestamp", Value::Int(0)),
+        ("AbsorptionAmount", Value::Float(0.0)),
+        (
+            "Attributes",
+            Value::List(vec![
+                attribute("minecraft:generic.max_health", 20.0),
+                attribute("minecraft:generic.movement_speed", 0.1),
+                attribute("minecraft:generic.attack_damage", 1.0),
+                attribute("minecraft:generic.attack_speed", 4.0),
+                attribute("minecraft:generic.luck", 0.0),
+            ]),
+        ),
+        ("Brain", compound([("memories", compound([]))])),
+        ("Inventory", Value::List((0..36).map(|slot| item(&mut rng, slot)).collect())),
+        ("EnderItems", Value::List((0..27).map(|slot| item(&mut rng, slot)).collect())),
+        ("SelectedItemSlot", Value::Int(0)),
+        ("XpLevel", Value::Int(30)),
+        ("XpP", Value::Float(0.25)),
+        ("XpTotal", Value::Int(1395)),
+        ("XpSeed", Value::Int(-1870328829)),
+        ("Score", Value::Int(1395)),
+        ("foodLevel", Value::Int(20)),
+        ("foodSaturationLevel", Value::Float(5.0)),
+        ("foodExhaustionLevel", Value::Float(0.0)),
+        ("foodTickTimer", Value::Int(0)),
+        (
+            "abilities",
+            compound([
+                ("walkSpeed", Value::Float(0.1)),
+                ("flySpeed", Value::Float(0.05)),
+                ("mayfly", Value::Byte(0)),
+                ("flying", Value::Byte(0)),
+                ("invulnerable", Value::Byte(0)),
+                ("mayBuild", Value::Byte(1)),
+                ("instabuild", Value::Byte(0)),
+            ]),
+        ),
+        ("playerGameType", Value::Int(0)),
+        ("previousPlayerGameType", Value::Int(-1)),
+        ("seenCredits", Value::Byte(0)),
+        ("Dimension", string("minecraft:overworld")),
+        (
+            "recipeBook",
+            compound([
+                ("recipes", Value::List(recipes.clone())),
+                ("toBeDisplayed", Value::List(recipes[..200].to_vec())),
+                ("isFurnaceGuiOpen", Value::Byte(0)),
+                ("isFurnaceFilteringCraftable", Value::Byte(0)),
+                ("isGuiOpen", Value::Byte(0)),
+                ("isFilteringCraftable", Value::Byte(0)),
+            ]),
+        ),
+    ])
+}
+
+/// A Java `level.dat`, with game rules, world generation settings and the singleplayer player.
+pub fn level_dat() -> Value {
+    let game_rules: Compound = (0..50)
+        .map(|i| (format!("gameRule{}", i), string(if i % 3 == 0 { "false" } else { "true" })))
+        .collect();
+    let dimension = |name: &str, generator: &str| {
+        (
+            format!("minecraft:{}", name),
+            compound([
+                ("type", string(format!("minecraft:{}", name))),
+                (
+                    "generator",
+                    compound([
+                        ("type", string("minecraft:noise")),
+                        ("settings", string(format!("minecraft:{}", generator))),
+                        ("biome_source", compound([("type", string("minecraft:multi_noise")), ("preset", string(format!("minecraft:{}", name)))])),
+                    ]),
+                ),
+            ]),
+        )
+    };
+    let dimensions: Compound =
+        [dimension("overworld", "overworld"), dimension("the_nether", "nether"), dimension("the_end", "end")].into_iter().collect();
+    let data = compound([
+        ("DataVersion", Value::Int(DATA_VERSION)),
+        ("version", Value::Int(19133)),
+        ("LevelName", string("New World")),
+        ("GameType", Value::Int(0)),
+        ("Difficulty", Value::Byte(2)),
+        ("DifficultyLocked", Value::Byte(0)),
+        ("hardcore", Value::Byte(0)),
+        ("allowCommands", Value::Byte(1)),
+        ("initialized", Value::Byte(1)),
+        ("Time", Value::Long(1_234_567)),
+        ("DayTime", Value::Long(6000)),
+        ("LastPlayed", Value::Long(1_700_000_000_000)),
+        ("SpawnX", Value::Int(-160)),
+        ("SpawnY", Value::Int(64)),
+        ("SpawnZ", Value::Int(304)),
+        ("SpawnAngle", Value::Float(0.0)),
+        ("clearWeatherTime", Value::Int(0)),
+        ("raining", Value::Byte(0)),
+        ("rainTime", Value::Int(43_518)),
+        ("thundering", Value::Byte(0)),
+        ("thunderTime", Value::Int(62_361)),
+        ("WanderingTraderSpawnChance", Value::Int(25)),
+        ("WanderingTraderSpawnDelay", Value::Int(19_200)),
+        ("BorderCenterX", Value::Double(0.0)),
+        ("BorderCenterZ", Value::Double(0.0)),
+        ("BorderSize", Value::Double(59_999_968.0)),
+        ("BorderDamagePerBlock", Value::Double(0.2)),
+        ("BorderSafeZone", Value::Double(5.0)),
+        ("BorderWarningBlocks", Value::Double(5.0)),
+        ("BorderWarningTime", Value::Double(15.0)),
+        ("ServerBrands", Value::List(vec![string("vanilla")])),
+        ("WasModded", Value::Byte(0)),
+        (
+            "Version",
+            compound([
+                ("Id", Value::Int(DATA_VERSION)),
+                ("Name", string("1.20.1")),
+                ("Series", string("main")),
+                ("Snapshot", Value::Byte(0)),
+            ]),
+        ),
+        (
+            "DataPacks",
+            compound([
+                ("Enabled", Value::List(vec![string("vanilla")])),
+                ("Disabled", Value::List(vec![string("bundle"), string("trade_rebalance")])),
+            ]),
+        ),
+        ("enabled_features", Value::List(vec![string("minecraft:vanilla")])),
+        ("GameRules", Value::Compound(game_rules)),
+        (
+            "WorldGenSettings",
+            compound([
+                ("seed", Value::Long(-4_172_144_997_902_289_642)),
+                ("generate_features", Value::Byte(1)),
+                ("bonus_chest", Value::Byte(0)),
+                ("dimensions", Value::Compound(dimensions)),
+            ]),
+        ),
+        ("DragonFight", compound([("NeedsStateScanning", Value::Byte(1)), ("DragonKilled", Value::Byte(0)), ("PreviouslyKilled", Value::Byte(0))])),
+        ("CustomBossEvents", compound([])),
+        ("ScheduledEvents", Value::List(Vec::new())),
+        ("Player", player()),
+    ]);
+    compound([("Data", data)])
+}
+
+/// Packs palette indices the way chunk sections do since 1.16: values never straddle longs.
+fn pack(rng: &mut Rng, count: usize, palette_len: usize, min_bits: u32) -> Vec<i64> {
+    let bits = (usize::BITS - (palette_len - 1).leading_zeros()).max(min_bits) as usize;
+    let per_long = 64 / bits;
+    (0..count.div_ceil(per_long))
+        .map(|_| (0..per_long).fold(0u64, |long, i| long | rng.below(palette_len as u64) << (i * bits)) as i64)
+        .collect()
+}
+
+fn section(rng: &mut Rng, y: i8) -> Value {
+    // underground sections are mixed, the rest is air
+    let palette_len = match y {
+        ..=-1 => 6 + rng.below(10) as usize,
+        0..=4 => 20 + rng.below(20) as usize,
+        _ => 1,
+    };
+    let palette = (0..palette_len)
+        .map(|i| match i {
+            0 if palette_len == 1 => compound([("Name", string("minecraft:air"))]),
+            _ if i % 4 == 0 => compound([
+                ("Name", string(format!("minecraft:block_{}", i))),
+                ("Properties", compound([("facing", string("north")), ("waterlogged", string("false"))])),
+            ]),
+            _ => compound([("Name", string(format!("minecraft:block_{}", i)))]),
+        })
+        .collect();
+    let mut block_states = compound([("palette", Value::List(palette))]);
+    if palette_len > 1 {
+        let data = Value::LongArray(pack(rng, 4096, palette_len, 4));
+        block_states.as_compound_mut().unwrap().insert("data".to_string(), data);
+    }
+    let biomes = match y {
+        ..=4 => compound([
+            ("palette", Value::List(vec![string("minecraft:plains"), string("minecraft:forest"), string("minecraft:river")])),
+            ("data", Value::LongArray(pack(rng, 64, 3, 1))),
+        ]),
+        _ => compound([("palette", Value::List(vec![string("minecraft:plains")]))]),
+    };
+    let light = |level: i8| Value::ByteArray(vec![level; 2048]);
+    compound([
+        ("Y", Value::Byte(y)),
+        ("block_states", block_states),
+        ("biomes", biomes),
+        ("BlockLight", light(0)),
+        ("SkyLight", light(if y > 4 { -1 } else { 0 })),
+    ])
+}
+
+/// A full-height 1.20 chunk from a region file: 24 sections, heightmaps, ticks and a few chests.
+pub fn chunk() -> Value {
+    let mut rng = Rng::new();
+    let sections = (-4..20).map(|y| section(&mut rng, y)).collect();
+    let mut heightmap = || Value::LongArray(pack(&mut rng, 256, 384, 9));
+    let heightmaps = compound([
+        ("MOTION_BLOCKING", heightmap()),
+        ("MOTION_BLOCKING_NO_LEAVES", heightmap()),
+        ("OCEAN_FLOOR", heightmap()),
+        ("WORLD_SURFACE", heightmap()),
+    ]);
+    let block_entities = (0..4)
+        .map(|i| {
+            compound([
+                ("id", string("minecraft:chest")),
+                ("x", Value::Int(i * 3)),
+                ("y", Value::Int(-20)),
+                ("z", Value::Int(7)),
+                ("keepPacked", Value::Byte(0)),
+                ("Items", Value::List((0..9).map(|slot| item(&mut rng, slot)).collect())),
+            ])
+        })
+        .collect();
+    let block_ticks = (0..16)
+        .map(|i| {
+            compound([
+                ("i", string("minecraft:water")),
+                ("x", Value::Int(i)),
+                ("y", Value::Int(62)),
+                ("z", Value::Int(i)),
+                ("t", Value::Int(5)),
+                ("p", Value::Int(0)),
+            ])
+        })
+        .collect();
+    compound([
+        ("DataVersion", Value::Int(DATA_VERSION)),
+        ("xPos", Value::Int(-10)),
+        ("yPos", Value::Int(-4)),
+        ("zPos", Value::Int(19)),
+        ("Status", string("minecraft:full")),
+        ("LastUpdate", Value::Long(1_234_000)),
+        ("InhabitedTime", Value::Long(48_213)),
+        ("isLightOn", Value::Byte(1)),
+        ("sections", Value::List(sections)),
+        ("Heightmaps", heightmaps),
+        ("block_entities", Value::List(block_entities)),
+        ("block_ticks", Value::List(block_ticks)),
+        ("fluid_ticks", Value::List(Vec::new())),
+        ("PostProcessing", Value::List((0..24).map(|_| Value::List(Vec::new())).collect())),
+        ("structures", compound([("References", compound([])), ("starts", compound([]))])),
+    ])
+}
+
+/// A structure block file of 32³ blocks, one compound per block as the format requires.
+pub fn structure() -> Value {
+    const SIZE: i32 = 32;
+    let mut rng = Rng::new();
+    let palette = (0..64)
+        .map(|i| {
+            compound([
+                ("Name", string(format!("minecraft:block_{}", i))),
+                ("Properties", compound([("axis", string(["x", "y", "z"][i % 3]))])),
+            ])
+        })
+        .collect();
+    let mut blocks = Vec::with_capacity((SIZE * SIZE * SIZE) as usize);
+    for x in 0..SIZE {
+        for y in 0..SIZE {
+            for z in 0..SIZE {
+                let mut block = compound([("state", Value::Int(rng.below(64) as i32)), ("pos", ints(&[x, y, z]))]);
+                if rng.below(512) == 0 {
+                    let nbt = compound([("id", string("minecraft:barrel")), ("Items", Value::List(vec![item(&mut rng, 0)]))]);
+                    block.as_compound_mut().unwrap().insert("nbt".to_string(), nbt);
+                }
+                blocks.push(block);
+            }
+        }
+    }
+    let entities = (0..8)
+        .map(|i| {
+            let pos = [i as f64 + 0.5, 1.0, 4.5];
+            compound([
+                ("pos", doubles(&pos)),
+                ("blockPos", ints(&[i, 1, 4])),
+                ("nbt", compound([("id", string("minecraft:armor_stand")), ("Pos", doubles(&pos)), ("Invisible", Value::Byte(0))])),
+            ])
+        })
+        .collect();
+    compound([
+        ("DataVersion", Value::Int(DATA_VERSION)),
+        ("size", ints(&[SIZE, SIZE, SIZE])),
+        ("palette", Value::List(palette)),
+        ("blocks", Value::List(blocks)),
+        ("entities", Value::List(entities)),
+    ])
+}
+
+/// Every corpus with its name, from smallest to largest.
+pub fn all() -> Vec<(&'static str, Value)> {
+    vec![("player", player()), ("level.dat", level_dat()), ("chunk", chunk()), ("structure", structure())]
+}
@@ -0,0 +1,137 @@
+//! Throughput and allocation counts of `Nbt::from_reader`, `Nbt::write_to` and a full round trip
+//! over the documents in `corpus`.
+//!
+//! Allocations are counted by a global allocator and reported through a Criterion measurement of
+//! their own, in the groups named `<corpus> allocs`. The counter adds one relaxed atomic add to
+//! every allocation in the timed groups as well.
+
+mod corpus;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use nbt::reader::NbtReader;
+use nbt::writer::NbtWriter;
+use nbt::{Nbt, NbtReadTrait, NbtWriteTrait, Value};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    // a growing `Vec` reallocates, which costs about as much as a fresh allocation
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Counts allocations and reallocations instead of measuring time.
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> usize {
+        ALLOCATIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, start: usize) -> usize {
+        ALLOCATIONS.load(Ordering::Relaxed) - start
+    }
+
+    fn add(&self, v1: &usize, v2: &usize) -> usize {
+        v1 + v2
+    }
+
+    fn zero(&self) -> usize {
+        0
+    }
+
+    fn to_f64(&self, value: &usize) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationFormatter
+    }
+}
+
+struct AllocationFormatter;
+
+impl ValueFormatter for AllocationFormatter {
+    fn scale_values(&self, _typical: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(&self, _typical: f64, _throughput: &Throughput, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+fn encode(value: &Value) -> Vec<u8> {
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, value).unwrap();
+    w.data
+}
+
+fn decode(data: &[u8]) -> Value {
+    Nbt::from_reader(&mut NbtReader::new(data)).unwrap()
+}
+
+fn corpora<M: Measurement>(c: &mut Criterion<M>, suffix: &str, throughput: bool) {
+    for (name, value) in corpus::all() {
+        let data = encode(&value);
+        let mut group = c.benchmark_group(format!("{}{}", name, suffix));
+        if throughput {
+            group.throughput(Throughput::Bytes(data.len() as u64));
+        }
+        group.bench_function("read", |b| b.iter(|| decode(black_box(&data))));
+        group.bench_function("write", |b| b.iter(|| encode(black_box(&value))));
+        group.bench_function("round_trip", |b| b.iter(|| encode(&decode(black_box(&data)))));
+        group.finish();
+    }
+}
+
+fn time(c: &mut Criterion) {
+    corpora(c, "", true);
+}
+
+fn allocations(c: &mut Criterion<Allocations>) {
+    corpora(c, " allocs", false);
+}
+
+criterion_group!(time_benches, time);
+criterion_group! {
+    name = allocation_benches;
+    // the counts are the same every iteration, so few samples are enough, and plotting their
+    // distribution would fail
+    config = Criterion::default().with_measurement(Allocations).sample_size(10).without_plots();
+    targets = allocations
+}
+criterion_main!(time_benches, allocation_benches);
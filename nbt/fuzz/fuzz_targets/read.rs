@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nbt::Nbt;
+
+fuzz_target!(|data: &[u8]| nbt_fuzz::check_read::<Nbt>(data));
@@ -0,0 +1,6 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nbt::NbtAfter764;
+
+fuzz_target!(|data: &[u8]| nbt_fuzz::check_read::<NbtAfter764>(data));
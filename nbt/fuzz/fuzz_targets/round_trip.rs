@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nbt::reader::NbtReader;
+use nbt::writer::NbtWriter;
+use nbt::{DialectKind, Error};
+use nbt_fuzz::Tree;
+
+// every dialect must read back exactly the tree it wrote
+fuzz_target!(|tree: Tree| {
+    for kind in [DialectKind::Nbt, DialectKind::NbtAfter764, DialectKind::BedrockNbt, DialectKind::BedrockNetworkNbt] {
+        let mut w = NbtWriter::new();
+        match kind.write(&mut w, &tree.0) {
+            Ok(()) => {}
+            // arbitrary strings may be longer than a string tag can hold
+            Err(Error::StringTooLong { .. }) => return,
+            Err(e) => panic!("{:?} failed to write: {}", kind, e),
+        }
+        let read = kind.read(&mut NbtReader::new(&w.data)).unwrap_or_else(|e| panic!("{:?} failed to read: {}", kind, e));
+        assert_eq!(read, tree.0, "{:?}", kind);
+    }
+});
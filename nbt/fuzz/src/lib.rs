@@ -0,0 +1,68 @@
+//! Shared checks for the fuzz targets. Run them with `cargo fuzz run <target>` from `nbt/`.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use nbt::reader::NbtReader;
+use nbt::writer::NbtWriter;
+use nbt::{Compound, NbtReadTrait, NbtWriteTrait, Value};
+
+// well below the reader's depth limit, which a valid tree must never hit
+const MAX_DEPTH: usize = 32;
+
+/// Reads `data` and, if it is a valid document, checks that the borrowing reader agrees and that
+/// writing it back gives bytes that read as the same tree.
+///
+/// The borrowing reader may accept more, since it skips the root name and decodes strings lazily.
+pub fn check_read<D: NbtReadTrait + NbtWriteTrait>(data: &[u8]) {
+    let Ok(value) = D::from_reader(&mut NbtReader::new(data)) else { return };
+    let borrowed = D::from_reader_ref(&mut NbtReader::new(data)).and_then(|v| v.to_owned());
+    assert_eq!(borrowed.expect("the borrowing reader accepts what the owning reader does"), value);
+    let mut w = NbtWriter::new();
+    D::write_to(&mut w, &value).expect("a tree that was read can be written");
+    let reread = D::from_reader(&mut NbtReader::new(&w.data)).expect("written bytes can be read");
+    assert_eq!(reread, value);
+}
+
+/// A root compound whose lists are all homogeneous, so that every dialect can write it.
+#[derive(Debug)]
+pub struct Tree(pub Value);
+
+impl<'a> Arbitrary<'a> for Tree {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Tree> {
+        Ok(Tree(compound(u, 0)?))
+    }
+}
+
+fn arbitrary_tag(u: &mut Unstructured, depth: usize) -> Result<u8> {
+    let tag = u.int_in_range(1..=12)?;
+    Ok(if depth >= MAX_DEPTH && matches!(tag, 9 | 10) { 1 } else { tag })
+}
+
+fn compound(u: &mut Unstructured, depth: usize) -> Result<Value> {
+    let mut compound = Compound::new();
+    for _ in 0..u.arbitrary_len::<(u8, u8)>()? {
+        let tag = arbitrary_tag(u, depth)?;
+        compound.insert(u.arbitrary()?, value(u, tag, depth + 1)?);
+    }
+    Ok(Value::Compound(compound))
+}
+
+fn value(u: &mut Unstructured, tag: u8, depth: usize) -> Result<Value> {
+    Ok(match tag {
+        1 => Value::Byte(u.arbitrary()?),
+        2 => Value::Short(u.arbitrary()?),
+        3 => Value::Int(u.arbitrary()?),
+        4 => Value::Long(u.arbitrary()?),
+        5 => Value::Float(u.arbitrary()?),
+        6 => Value::Double(u.arbitrary()?),
+        7 => Value::ByteArray(u.arbitrary()?),
+        8 => Value::String(u.arbitrary()?),
+        9 => {
+            let element = arbitrary_tag(u, depth)?;
+            let len = u.arbitrary_len::<u8>()?;
+            Value::List((0..len).map(|_| value(u, element, depth + 1)).collect::<Result<_>>()?)
+        }
+        10 => compound(u, depth)?,
+        11 => Value::IntArray(u.arbitrary()?),
+        _ => Value::LongArray(u.arbitrary()?),
+    })
+}
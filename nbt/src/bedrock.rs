@@ -0,0 +1,59 @@
+use crate::reader::{NbtReader, NbtSource};
+use crate::writer::NbtWriter;
+use crate::{NbtReadTrait, NbtWriteTrait, Result, Value};
+
+/// Bedrock Edition's little-endian NBT, used in LevelDB values and `level.dat`.
+///
+/// Strings are plain UTF-8 with a 16-bit length, and the root compound is named.
+#[derive(Default)]
+pub struct BedrockNbt;
+
+/// Bedrock Edition's network NBT: ints and longs, including lengths, are ZigZag VarInts,
+/// string lengths are unsigned VarInts and everything else is little-endian.
+#[derive(Default)]
+pub struct BedrockNetworkNbt;
+
+impl BedrockNbt {
+    pub fn new() -> BedrockNbt {
+        BedrockNbt
+    }
+}
+
+impl BedrockNetworkNbt {
+    pub fn new() -> BedrockNetworkNbt {
+        BedrockNetworkNbt
+    }
+}
+
+pub const LEVEL_DAT_HEADER_LEN: usize = 8;
+
+/// Reads a `level.dat`: the storage version and payload length as little-endian ints,
+/// followed by a little-endian document. Returns the storage version and the document.
+pub fn read_level_dat(data: &[u8]) -> Result<(i32, Value)> {
+    let mut r = NbtReader::new(data);
+    let version = i32::from_le_bytes(r.read_array()?);
+    let len = i32::from_le_bytes(r.read_array()?);
+    let len = r.accounter.check_len(len)?;
+    let payload = r.take(len)?;
+    Ok((version, BedrockNbt::from_reader(&mut NbtReader::new(payload))?))
+}
+
+pub fn write_level_dat(version: i32, value: &Value) -> Result<Vec<u8>> {
+    let mut w = NbtWriter::new();
+    BedrockNbt::write_to(&mut w, value)?;
+    let mut data = Vec::with_capacity(LEVEL_DAT_HEADER_LEN + w.data.len());
+    data.extend_from_slice(&version.to_le_bytes());
+    data.extend_from_slice(&(w.data.len() as u32).to_le_bytes());
+    data.extend_from_slice(&w.data);
+    Ok(data)
+}
+
+/// Reads every document in `data`; LevelDB values such as block entity lists hold several in a row.
+pub fn read_all(data: &[u8]) -> Result<Vec<Value>> {
+    let mut r = NbtReader::new(data);
+    let mut values = Vec::new();
+    while r.remaining() > 0 {
+        values.push(BedrockNbt::from_reader(&mut r)?);
+    }
+    Ok(values)
+}
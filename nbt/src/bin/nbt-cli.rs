@@ -0,0 +1,272 @@
+//! `nbt-cli`: inspect and edit NBT files without a GUI editor.
+//!
+//! Input framing (gzip, zlib, LZ4 or raw) and dialect (Java, Bedrock, Bedrock `level.dat`, and
+//! the network forms of both) are detected, and edited files are written back the way they were
+//! read. SNBT and JSON files made by `convert` are accepted as input too.
+
+use std::fs;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use nbt::bedrock::{read_level_dat, write_level_dat, LEVEL_DAT_HEADER_LEN};
+use nbt::compression::{compress, decompress};
+use nbt::reader::NbtReader;
+use nbt::writer::NbtWriter;
+use nbt::{diff, json, snbt, Compression, DialectKind, NamedTag, NbtPath, TreeOptions, Value};
+
+const USAGE: &str = "\
+usage: nbt-cli <command> [options]
+
+commands:
+  dump <file> [--snbt] [--color]      print the file as a tree, or as SNBT
+  get <file> <path>                   print every tag the path selects
+  set <file> <path> <snbt> [-o out]   set the selected tags and save
+  delete <file> <path> [-o out]       remove the selected tags and save
+  diff <a> <b>                        list what changed from a to b
+  convert <file> --to json|snbt|nbt [-o out]
+      --plain                         plain JSON for display instead of typed JSON
+      --compression gzip|zlib|lz4|none   framing for --to nbt (default gzip)
+      --dialect java|java-network|bedrock|bedrock-network
+                                      dialect for --to nbt (default java); the network
+                                      dialects are the unnamed 1.20.2+ root and Bedrock's
+                                      VarInt encoding
+
+Paths use the /data command syntax, such as Inventory[{Slot:0b}].Count.
+Files are edited in place unless -o is given.";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Binary(DialectKind),
+    LevelDat(i32),
+    Snbt,
+    Json,
+}
+
+struct Document {
+    tag: NamedTag,
+    format: Format,
+    compression: Compression,
+}
+
+struct Args {
+    positional: Vec<String>,
+    flags: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args { positional: Vec::new(), flags: Vec::new(), options: Vec::new() };
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" | "--to" | "--compression" | "--dialect" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                    parsed.options.push((arg, value));
+                }
+                "--snbt" | "--color" | "--plain" => parsed.flags.push(arg),
+                s if s.starts_with("--") => return Err(format!("unknown option {}", s)),
+                _ => parsed.positional.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn option(&self, names: &[&str]) -> Option<&str> {
+        self.options.iter().rev().find(|(k, _)| names.contains(&k.as_str())).map(|(_, v)| v.as_str())
+    }
+
+    fn expect(&self, count: usize) -> Result<&[String], String> {
+        match self.positional.len() - 1 {
+            n if n == count => Ok(&self.positional[1..]),
+            n => Err(format!("{} takes {} arguments, got {}", self.positional[0], count, n)),
+        }
+    }
+}
+
+fn parse_text(text: &str) -> Result<(Value, Format), String> {
+    if let Ok(json) = serde_json::from_str::<serde_json::Value>(text) {
+        let typed = json.get("type").is_some();
+        let value = if typed { json::from_typed(&json) } else { json::from_plain(&json) };
+        return value.map(|v| (v, Format::Json)).map_err(|e| e.to_string());
+    }
+    snbt::from_str(text).map(|v| (v, Format::Snbt)).map_err(|e| e.to_string())
+}
+
+fn load(path: &str) -> Result<Document, String> {
+    let raw = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let compression = Compression::detect(&raw);
+    let data = decompress(&raw, compression).map_err(|e| format!("{}: {}", path, e))?;
+    let document = |tag, format| Ok(Document { tag, format, compression });
+
+    // binary NBT opens with 0x0a, itself whitespace, so what looks like text may be binary after all
+    let mut text_error = None;
+    if compression == Compression::None && matches!(data.iter().find(|b| !b.is_ascii_whitespace()), Some(b'{' | b'[')) {
+        match std::str::from_utf8(&data).map_err(|e| e.to_string()).and_then(parse_text) {
+            Ok((value, format)) => return document(NamedTag::new("", value), format),
+            Err(e) => text_error = Some(e),
+        }
+    }
+    let header_len = data.get(4..8).map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize);
+    if header_len == Some(data.len().saturating_sub(LEVEL_DAT_HEADER_LEN)) && data.get(8) == Some(&10) {
+        if let Ok((version, value)) = read_level_dat(&data) {
+            return document(NamedTag::new("", value), Format::LevelDat(version));
+        }
+    }
+    // file dialects first, as a network document is rarely also a valid file
+    for kind in [DialectKind::Nbt, DialectKind::BedrockNbt, DialectKind::NbtAfter764, DialectKind::BedrockNetworkNbt] {
+        let mut r = NbtReader::new(&data);
+        if let Ok(tag) = kind.read_named(&mut r) {
+            if r.remaining() == 0 {
+                return document(tag, Format::Binary(kind));
+            }
+        }
+    }
+    match text_error {
+        Some(e) => Err(format!("{}: {}", path, e)),
+        None => Err(format!("{}: not a Java or Bedrock NBT file", path)),
+    }
+}
+
+fn encode(document: &Document) -> Result<Vec<u8>, String> {
+    let tag = &document.tag;
+    let data = match document.format {
+        Format::Snbt => return Ok(format!("{:#}\n", tag.value).into_bytes()),
+        Format::Json => {
+            let json = serde_json::to_string_pretty(&json::to_typed(&tag.value)).map_err(|e| e.to_string())?;
+            return Ok((json + "\n").into_bytes());
+        }
+        Format::LevelDat(version) => write_level_dat(version, &tag.value).map_err(|e| e.to_string())?,
+        Format::Binary(kind) => {
+            let mut w = NbtWriter::new();
+            kind.write_named(&mut w, tag).map_err(|e| e.to_string())?;
+            w.data
+        }
+    };
+    compress(&data, document.compression).map_err(|e| e.to_string())
+}
+
+fn output(path: Option<&str>, data: &[u8]) -> Result<(), String> {
+    match path {
+        Some(path) => fs::write(path, data).map_err(|e| format!("{}: {}", path, e)),
+        None => io::stdout().write_all(data).map_err(|e| e.to_string()),
+    }
+}
+
+fn parse_path(path: &str) -> Result<NbtPath, String> {
+    NbtPath::parse(path).map_err(|e| e.to_string())
+}
+
+fn run(args: &Args) -> Result<ExitCode, String> {
+    let command = args.positional.first().ok_or("missing command")?;
+    match command.as_str() {
+        "dump" => {
+            let [file] = args.expect(1)? else { unreachable!() };
+            let value = load(file)?.tag.value;
+            if args.flag("--snbt") {
+                println!("{:#}", value);
+            } else {
+                let options = TreeOptions { color: args.flag("--color"), ..TreeOptions::default() };
+                println!("{}", value.tree(options));
+            }
+        }
+        "get" => {
+            let [file, path] = args.expect(2)? else { unreachable!() };
+            let value = load(file)?.tag.value;
+            let found = parse_path(path)?.get(&value);
+            if found.is_empty() {
+                return Err(format!("nothing matches {}", path));
+            }
+            for v in found {
+                println!("{:#}", v);
+            }
+        }
+        "set" | "delete" => {
+            let (file, path, new) = match command.as_str() {
+                "set" => {
+                    let [file, path, new] = args.expect(3)? else { unreachable!() };
+                    (file, path, Some(snbt::from_str(new).map_err(|e| e.to_string())?))
+                }
+                _ => {
+                    let [file, path] = args.expect(2)? else { unreachable!() };
+                    (file, path, None)
+                }
+            };
+            let mut document = load(file)?;
+            let path = parse_path(path)?;
+            let count = match new {
+                Some(new) => path.set(&mut document.tag.value, new),
+                None => path.remove(&mut document.tag.value),
+            };
+            if count == 0 {
+                return Err(format!("nothing matches {}", path));
+            }
+            fs::write(args.option(&["-o", "--output"]).unwrap_or(file), encode(&document)?).map_err(|e| e.to_string())?;
+            eprintln!("{} {} tag{}", if command == "set" { "set" } else { "removed" }, count, if count == 1 { "" } else { "s" });
+        }
+        "diff" => {
+            let [a, b] = args.expect(2)? else { unreachable!() };
+            let patch = diff(&load(a)?.tag.value, &load(b)?.tag.value);
+            print!("{}", patch);
+            return Ok(if patch.is_empty() { ExitCode::SUCCESS } else { ExitCode::from(1) });
+        }
+        "convert" => {
+            let [file] = args.expect(1)? else { unreachable!() };
+            let mut document = load(file)?;
+            let out = args.option(&["-o", "--output"]);
+            match args.option(&["--to"]).ok_or("convert needs --to")? {
+                "json" if args.flag("--plain") => {
+                    let json = serde_json::to_string_pretty(&json::to_plain(&document.tag.value)).map_err(|e| e.to_string())?;
+                    return output(out, (json + "\n").as_bytes()).map(|_| ExitCode::SUCCESS);
+                }
+                "json" => document.format = Format::Json,
+                "snbt" => document.format = Format::Snbt,
+                "nbt" => {
+                    document.format = match args.option(&["--dialect"]).unwrap_or("java") {
+                        "java" => Format::Binary(DialectKind::Nbt),
+                        "java-network" => Format::Binary(DialectKind::NbtAfter764),
+                        "bedrock" => Format::Binary(DialectKind::BedrockNbt),
+                        "bedrock-network" => Format::Binary(DialectKind::BedrockNetworkNbt),
+                        x => return Err(format!("unknown dialect {}", x)),
+                    };
+                    document.compression = match args.option(&["--compression"]).unwrap_or("gzip") {
+                        "gzip" => Compression::Gzip,
+                        "zlib" => Compression::Zlib,
+                        "lz4" => Compression::Lz4,
+                        "none" => Compression::None,
+                        x => return Err(format!("unknown compression {}", x)),
+                    };
+                }
+                x => return Err(format!("cannot convert to {}", x)),
+            }
+            output(out, &encode(&document)?)?;
+        }
+        x => return Err(format!("unknown command {}", x)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) if !args.positional.is_empty() => args,
+        Ok(_) => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+        Err(e) => {
+            eprintln!("nbt-cli: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("nbt-cli: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::BTreeMap;
+
+use crate::{Compound, Error, Result, Value};
+
+pub const SECTION_BLOCKS: usize = 4096;
+pub const SECTION_BIOMES: usize = 64;
+/// The first data version (20w17a) whose packed arrays never split an entry across two longs.
+pub const PADDED_DATA_VERSION: i32 = 2529;
+/// The first data version (21w43a) with sections at the root and paletted biomes.
+pub const SECTIONS_DATA_VERSION: i32 = 2844;
+
+const AIR: &str = "minecraft:air";
+const BLOCK_MIN_BITS: usize = 4;
+const BIOME_MIN_BITS: usize = 1;
+
+/// How entries of a packed `LongArray` are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packing {
+    /// Entries follow each other bit by bit and may span two longs, as before 1.16.
+    Spanning,
+    /// Each long holds as many whole entries as fit and leaves the rest of its bits unused.
+    Padded,
+}
+
+impl Packing {
+    pub fn for_data_version(data_version: i32) -> Packing {
+        if data_version < PADDED_DATA_VERSION {
+            Packing::Spanning
+        } else {
+            Packing::Padded
+        }
+    }
+
+    /// The number of longs holding `len` entries of `bits` bits each.
+    pub fn longs(self, len: usize, bits: usize) -> usize {
+        match self {
+            Packing::Spanning => (len * bits).div_ceil(64),
+            Packing::Padded => len.div_ceil(64 / bits),
+        }
+    }
+}
+
+/// Unpacks `len` entries of `bits` bits from `data`.
+pub fn unpack(data: &[i64], bits: usize, len: usize, packing: Packing) -> Result<Vec<u16>> {
+    if !(1..=16).contains(&bits) {
+        return Err(Error::Chunk(format!("Cannot unpack entries of {} bits", bits)));
+    }
+    let expected = packing.longs(len, bits);
+    if data.len() != expected {
+        return Err(Error::Chunk(format!("Expected {} longs for {} entries of {} bits, found {}", expected, len, bits, data.len())));
+    }
+    let mask = (1u64 << bits) - 1;
+    let per_long = 64 / bits;
+    let values = (0..len).map(|i| {
+        let value = match packing {
+            Packing::Spanning => {
+                let (long, offset) = (i * bits / 64, i * bits % 64);
+                let mut value = data[long] as u64 >> offset;
+                if offset + bits > 64 {
+                    value |= (data[long + 1] as u64) << (64 - offset);
+                }
+                value
+            }
+            Packing::Padded => data[i / per_long] as u64 >> (i % per_long * bits),
+        };
+        (value & mask) as u16
+    });
+    Ok(values.collect())
+}
+
+/// Packs `values` into longs of `bits` bits per entry; the inverse of [`unpack`].
+pub fn pack(values: &[u16], bits: usize, packing: Packing) -> Vec<i64> {
+    assert!((1..=16).contains(&bits), "cannot pack entries of {} bits", bits);
+    let mut data = vec![0u64; packing.longs(values.len(), bits)];
+    let per_long = 64 / bits;
+    for (i, &value) in values.iter().enumerate() {
+        let value = value as u64 & ((1 << bits) - 1);
+        match packing {
+            Packing::Spanning => {
+                let (long, offset) = (i * bits / 64, i * bits % 64);
+                data[long] |= value << offset;
+                if offset + bits > 64 {
+                    data[long + 1] |= value >> (64 - offset);
+                }
+            }
+            Packing::Padded => data[i / per_long] |= value << (i % per_long * bits),
+        }
+    }
+    data.into_iter().map(|v| v as i64).collect()
+}
+
+// The bits vanilla stores a palette of `len` entries with: none for a single entry.
+fn bits_for(len: usize, min_bits: usize) -> usize {
+    match len {
+        0 | 1 => 0,
+        n => (usize::BITS - (n - 1).leading_zeros()).max(min_bits as u32) as usize,
+    }
+}
+
+fn field<'a>(compound: &'a Compound, key: &str) -> Result<&'a Value> {
+    compound.get(key).ok_or_else(|| Error::MissingField(key.to_string()))
+}
+
+fn expect<'a, T>(value: &'a Value, tag: u8, get: impl FnOnce(&'a Value) -> Option<T>) -> Result<T> {
+    get(value).ok_or(Error::TagMismatch { expected: tag, found: value.tag() })
+}
+
+fn into_compound(value: Value) -> Result<Compound> {
+    match value {
+        Value::Compound(c) => Ok(c),
+        x => Err(Error::TagMismatch { expected: 10, found: x.tag() }),
+    }
+}
+
+/// A block and its properties, as in a `block_states` palette.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockState {
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+impl BlockState {
+    pub fn new(name: impl Into<String>) -> BlockState {
+        BlockState { name: name.into(), properties: BTreeMap::new() }
+    }
+
+    pub fn air() -> BlockState {
+        BlockState::new(AIR)
+    }
+
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> BlockState {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    pub fn from_value(value: &Value) -> Result<BlockState> {
+        let compound = expect(value, 10, Value::as_compound)?;
+        let name = expect(field(compound, "Name")?, 8, Value::as_str)?.to_string();
+        let mut properties = BTreeMap::new();
+        if let Some(props) = compound.get("Properties") {
+            for (key, value) in expect(props, 10, Value::as_compound)?.iter() {
+                properties.insert(key.clone(), expect(value, 8, Value::as_str)?.to_string());
+            }
+        }
+        Ok(BlockState { name, properties })
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut compound = Compound::new();
+        compound.insert("Name".to_string(), Value::String(self.name.clone()));
+        if !self.properties.is_empty() {
+            let properties = self.properties.iter().map(|(k, v)| (k.clone(), Value::String(v.clone())));
+            compound.insert("Properties".to_string(), Value::Compound(properties.collect()));
+        }
+        Value::Compound(compound)
+    }
+}
+
+/// A fixed number of entries stored as indices into a palette of distinct values.
+///
+/// Entries are kept unpacked; unused palette entries are only dropped when encoding.
+#[derive(Debug, Clone)]
+pub struct PalettedContainer<T> {
+    palette: Vec<T>,
+    indices: Vec<u16>,
+}
+
+/// Containers are equal when their entries are, however their palettes are ordered.
+impl<T: Clone + PartialEq> PartialEq for PalettedContainer<T> {
+    fn eq(&self, other: &PalettedContainer<T>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone + PartialEq> PalettedContainer<T> {
+    /// `len` entries that are all `value`.
+    pub fn filled(value: T, len: usize) -> PalettedContainer<T> {
+        PalettedContainer { palette: vec![value], indices: vec![0; len] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// The palette, which may still hold values no entry refers to.
+    pub fn palette(&self) -> &[T] {
+        &self.palette
+    }
+
+    pub fn get(&self, i: usize) -> &T {
+        &self.palette[self.indices[i] as usize]
+    }
+
+    /// Sets entry `i`, adding `value` to the palette if needed, and returns the old value.
+    pub fn set(&mut self, i: usize, value: T) -> T {
+        let index = match self.palette.iter().position(|v| *v == value) {
+            Some(index) => index,
+            None => {
+                // there can't be more values in use than entries, so this keeps indices in range
+                if self.palette.len() > self.indices.len() {
+                    self.compact();
+                }
+                self.palette.push(value);
+                self.palette.len() - 1
+            }
+        };
+        let old = std::mem::replace(&mut self.indices[i], index as u16);
+        self.palette[old as usize].clone()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.indices.iter().map(|&i| &self.palette[i as usize])
+    }
+
+    /// Decodes `len` entries from a palette and the indices packed in `data`, which may be
+    /// absent when the palette has a single entry.
+    pub fn from_parts(palette: Vec<T>, data: Option<&[i64]>, len: usize, min_bits: usize, packing: Packing) -> Result<PalettedContainer<T>> {
+        if palette.is_empty() {
+            return Err(Error::Chunk("Empty palette".to_string()));
+        }
+        let bits = bits_for(palette.len(), min_bits);
+        let indices = match data {
+            // a single entry palette may still come with data, which is then all zeros
+            Some(data) => unpack(data, bits.max(min_bits), len, packing)?,
+            None if bits == 0 => vec![0; len],
+            None => return Err(Error::MissingField("data".to_string())),
+        };
+        if let Some(&i) = indices.iter().find(|&&i| i as usize >= palette.len()) {
+            return Err(Error::Chunk(format!("Palette index {} is out of bounds for {} entries", i, palette.len())));
+        }
+        Ok(PalettedContainer { palette, indices })
+    }
+
+    /// Reads a `{palette: [...], data: [L; ...]}` compound, where `data` is absent when the
+    /// palette has a single entry.
+    pub fn from_value(value: &Value, len: usize, min_bits: usize, packing: Packing, entry: impl Fn(&Value) -> Result<T>) -> Result<PalettedContainer<T>> {
+        let compound = expect(value, 10, Value::as_compound)?;
+        let palette = expect(field(compound, "palette")?, 9, Value::as_list)?.iter().map(entry).collect::<Result<Vec<T>>>()?;
+        let data = compound.get("data").map(|data| expect(data, 12, Value::as_long_array)).transpose()?;
+        PalettedContainer::from_parts(palette, data, len, min_bits, packing)
+    }
+
+    /// The palette of only the values in use, in their current order, and the indices packed
+    /// with the fewest bits vanilla allows; there are no indices when a single value is in use.
+    pub fn to_parts(&self, min_bits: usize, packing: Packing) -> (Vec<&T>, Option<Vec<i64>>) {
+        let (used, remap) = self.used();
+        let bits = bits_for(used.len(), min_bits);
+        let data = (bits > 0).then(|| {
+            let indices: Vec<u16> = self.indices.iter().map(|&i| remap[i as usize]).collect();
+            pack(&indices, bits, packing)
+        });
+        (used.into_iter().map(|i| &self.palette[i]).collect(), data)
+    }
+
+    /// Writes the container as a `{palette: [...], data: [L; ...]}` compound, see [`to_parts`](Self::to_parts).
+    pub fn to_value(&self, min_bits: usize, packing: Packing, entry: impl Fn(&T) -> Value) -> Value {
+        let (palette, data) = self.to_parts(min_bits, packing);
+        let mut compound = Compound::new();
+        compound.insert("palette".to_string(), Value::List(palette.into_iter().map(entry).collect()));
+        if let Some(data) = data {
+            compound.insert("data".to_string(), Value::LongArray(data));
+        }
+        Value::Compound(compound)
+    }
+
+    /// Drops palette values that no entry refers to.
+    pub fn compact(&mut self) {
+        let (used, remap) = self.used();
+        self.palette = used.into_iter().map(|i| self.palette[i].clone()).collect();
+        for i in &mut self.indices {
+            *i = remap[*i as usize];
+        }
+    }
+
+    // The palette indices in use, in palette order, and where each one moves when the rest are dropped.
+    fn used(&self) -> (Vec<usize>, Vec<u16>) {
+        let mut in_use = vec![false; self.palette.len()];
+        for &i in &self.indices {
+            in_use[i as usize] = true;
+        }
+        let used: Vec<usize> = (0..self.palette.len()).filter(|&i| in_use[i]).collect();
+        let mut remap = vec![0; self.palette.len()];
+        for (new, &old) in used.iter().enumerate() {
+            remap[old] = new as u16;
+        }
+        (used, remap)
+    }
+}
+
+/// A 16×16×16 section of a chunk.
+///
+/// The sections just above and below the world only hold light, so their block states and
+/// biomes are absent. Before 1.18 sections have no biomes either.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub y: i32,
+    pub block_states: Option<PalettedContainer<BlockState>>,
+    /// Biomes of each 4×4×4 cell.
+    pub biomes: Option<PalettedContainer<String>>,
+    /// Everything else, such as `BlockLight` and `SkyLight`.
+    pub other: Compound,
+}
+
+fn block_index(x: usize, y: i32, z: usize) -> Option<usize> {
+    (x < 16 && z < 16).then(|| (y.rem_euclid(16) as usize) << 8 | z << 4 | x)
+}
+
+fn biome_index(x: usize, y: i32, z: usize) -> Option<usize> {
+    (x < 16 && z < 16).then(|| (y.rem_euclid(16) as usize >> 2) << 4 | (z >> 2) << 2 | x >> 2)
+}
+
+fn outside(x: usize, y: i32, z: usize) -> Error {
+    Error::Chunk(format!("Block ({}, {}, {}) is outside the chunk", x, y, z))
+}
+
+impl Section {
+    /// A section without blocks or biomes.
+    pub fn new(y: i32) -> Section {
+        Section { y, block_states: None, biomes: None, other: Compound::new() }
+    }
+
+    /// Reads a section in the layout of `data_version`.
+    pub fn from_value(value: Value, data_version: i32) -> Result<Section> {
+        let mut other = into_compound(value)?;
+        let y = expect(field(&other, "Y")?, 1, Value::as_i8)? as i32;
+        other.remove("Y");
+        let packing = Packing::for_data_version(data_version);
+        if data_version < SECTIONS_DATA_VERSION {
+            let block_states = match other.remove("Palette") {
+                Some(palette) => {
+                    let palette = expect(&palette, 9, Value::as_list)?.iter().map(BlockState::from_value).collect::<Result<Vec<_>>>()?;
+                    let data = other.remove("BlockStates");
+                    let data = data.as_ref().map(|data| expect(data, 12, Value::as_long_array)).transpose()?;
+                    Some(PalettedContainer::from_parts(palette, data, SECTION_BLOCKS, BLOCK_MIN_BITS, packing)?)
+                }
+                None => None,
+            };
+            return Ok(Section { y, block_states, biomes: None, other });
+        }
+
+        let block_states = match other.remove("block_states") {
+            Some(v) => Some(PalettedContainer::from_value(&v, SECTION_BLOCKS, BLOCK_MIN_BITS, packing, BlockState::from_value)?),
+            None => None,
+        };
+        let biome = |v: &Value| expect(v, 8, Value::as_str).map(str::to_string);
+        let biomes = match other.remove("biomes") {
+            Some(v) => Some(PalettedContainer::from_value(&v, SECTION_BIOMES, BIOME_MIN_BITS, packing, biome)?),
+            None => None,
+        };
+        Ok(Section { y, block_states, biomes, other })
+    }
+
+    /// Writes the section in the layout of `data_version`; biomes are dropped before 1.18.
+    pub fn to_value(&self, data_version: i32) -> Value {
+        let packing = Packing::for_data_version(data_version);
+        let mut compound = Compound::with_capacity(self.other.len() + 3);
+        compound.insert("Y".to_string(), Value::Byte(self.y as i8));
+        if data_version < SECTIONS_DATA_VERSION {
+            if let Some(blocks) = &self.block_states {
+                // these versions always store the indices, even for a single block state
+                let (palette, data) = blocks.to_parts(BLOCK_MIN_BITS, packing);
+                let data = data.unwrap_or_else(|| vec![0; packing.longs(SECTION_BLOCKS, BLOCK_MIN_BITS)]);
+                compound.insert("Palette".to_string(), Value::List(palette.into_iter().map(BlockState::to_value).collect()));
+                compound.insert("BlockStates".to_string(), Value::LongArray(data));
+            }
+        } else {
+            if let Some(blocks) = &self.block_states {
+                compound.insert("block_states".to_string(), blocks.to_value(BLOCK_MIN_BITS, packing, BlockState::to_value));
+            }
+            if let Some(biomes) = &self.biomes {
+                compound.insert("biomes".to_string(), biomes.to_value(BIOME_MIN_BITS, packing, |b| Value::String(b.clone())));
+            }
+        }
+        compound.extend(self.other.iter().cloned());
+        Value::Compound(compound)
+    }
+
+    /// The block at chunk-local `x` and `z` and a `y` within this section, absolute or not.
+    pub fn get_block(&self, x: usize, y: i32, z: usize) -> Option<&BlockState> {
+        Some(self.block_states.as_ref()?.get(block_index(x, y, z)?))
+    }
+
+    /// Sets a block, filling the section with air first if it has no blocks, and returns the
+    /// block it replaced.
+    pub fn set_block(&mut self, x: usize, y: i32, z: usize, state: BlockState) -> Result<BlockState> {
+        let i = block_index(x, y, z).ok_or_else(|| outside(x, y, z))?;
+        let blocks = self.block_states.get_or_insert_with(|| PalettedContainer::filled(BlockState::air(), SECTION_BLOCKS));
+        Ok(blocks.set(i, state))
+    }
+
+    pub fn get_biome(&self, x: usize, y: i32, z: usize) -> Option<&str> {
+        Some(self.biomes.as_ref()?.get(biome_index(x, y, z)?))
+    }
+}
+
+/// A chunk as stored in region files, with its sections decoded.
+///
+/// Everything besides the position and the sections is kept in `other` as it was read. Before
+/// 1.18 the chunk is nested in a `Level` compound, which stays in `other` without the position
+/// and sections, along with the numeric `Biomes` of that layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub data_version: i32,
+    pub x: i32,
+    pub z: i32,
+    /// Sections ordered by `y`.
+    pub sections: Vec<Section>,
+    pub other: Compound,
+}
+
+// The compound holding the position and sections: the root since 1.18, `Level` before.
+fn level(root: &mut Compound, data_version: i32) -> Result<&mut Compound> {
+    if data_version >= SECTIONS_DATA_VERSION {
+        return Ok(root);
+    }
+    match root.get_mut("Level") {
+        Some(Value::Compound(level)) => Ok(level),
+        Some(x) => Err(Error::TagMismatch { expected: 10, found: x.tag() }),
+        None => Err(Error::MissingField("Level".to_string())),
+    }
+}
+
+impl Chunk {
+    pub fn from_value(value: Value) -> Result<Chunk> {
+        let mut other = into_compound(value)?;
+        let data_version = expect(field(&other, "DataVersion")?, 3, Value::as_i32)?;
+        other.remove("DataVersion");
+        let level = level(&mut other, data_version)?;
+        let x = expect(field(level, "xPos")?, 3, Value::as_i32)?;
+        let z = expect(field(level, "zPos")?, 3, Value::as_i32)?;
+        let key = if data_version < SECTIONS_DATA_VERSION { "Sections" } else { "sections" };
+        let mut sections = match level.remove(key) {
+            Some(Value::List(sections)) => sections.into_iter().map(|s| Section::from_value(s, data_version)).collect::<Result<Vec<_>>>()?,
+            Some(x) => return Err(Error::TagMismatch { expected: 9, found: x.tag() }),
+            None => return Err(Error::MissingField(key.to_string())),
+        };
+        sections.sort_by_key(|s| s.y);
+        level.remove("xPos");
+        level.remove("zPos");
+        Ok(Chunk { data_version, x, z, sections, other })
+    }
+
+    /// Encodes the chunk in the layout of its data version, with minimal palettes.
+    pub fn to_value(&self) -> Value {
+        let sections = Value::List(self.sections.iter().map(|s| s.to_value(self.data_version)).collect());
+        let mut compound = Compound::with_capacity(self.other.len() + 4);
+        compound.insert("DataVersion".to_string(), Value::Int(self.data_version));
+        let mut level = Compound::new();
+        level.insert("xPos".to_string(), Value::Int(self.x));
+        level.insert("zPos".to_string(), Value::Int(self.z));
+        if self.data_version < SECTIONS_DATA_VERSION {
+            level.insert("Sections".to_string(), sections);
+            if let Some(Value::Compound(rest)) = self.other.get("Level") {
+                level.extend(rest.iter().cloned());
+            }
+            compound.extend(self.other.iter().cloned());
+            compound.insert("Level".to_string(), Value::Compound(level));
+        } else {
+            level.insert("sections".to_string(), sections);
+            compound.extend(level);
+            compound.extend(self.other.iter().cloned());
+        }
+        Value::Compound(compound)
+    }
+
+    /// The section holding blocks at absolute height `y`.
+    pub fn section(&self, y: i32) -> Option<&Section> {
+        self.sections.iter().find(|s| s.y == y >> 4)
+    }
+
+    pub fn section_mut(&mut self, y: i32) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|s| s.y == y >> 4)
+    }
+
+    /// The block at chunk-local `x` and `z` and absolute `y`, if its section has blocks.
+    pub fn get_block(&self, x: usize, y: i32, z: usize) -> Option<&BlockState> {
+        self.section(y)?.get_block(x, y, z)
+    }
+
+    /// Sets a block, adding an air-filled section if there is none yet, and returns the block
+    /// it replaced.
+    pub fn set_block(&mut self, x: usize, y: i32, z: usize, state: BlockState) -> Result<BlockState> {
+        // section heights are stored as bytes
+        let section = i8::try_from(y >> 4).map_err(|_| outside(x, y, z))? as i32;
+        if block_index(x, y, z).is_none() {
+            return Err(outside(x, y, z));
+        }
+        let i = match self.sections.binary_search_by_key(&section, |s| s.y) {
+            Ok(i) => i,
+            Err(i) => {
+                self.sections.insert(i, Section::new(section));
+                i
+            }
+        };
+        self.sections[i].set_block(x, y, z, state)
+    }
+
+    pub fn get_biome(&self, x: usize, y: i32, z: usize) -> Option<&str> {
+        self.section(y)?.get_biome(x, y, z)
+    }
+}
@@ -0,0 +1,405 @@
+use crate::dialect::min_payload_size;
+use crate::reader::{NbtReader, NbtSource, COMPOUND_ENTRY_SIZE, TAG_SIZES};
+use crate::writer::{checked_len, NbtSink, NbtWriter};
+use crate::{Error, Nbt, NbtReadTrait, NbtWriteTrait, Result, Value};
+
+/// A type written straight to an [`NbtSink`], without building a [`Value`] first.
+///
+/// Usually derived with `#[derive(ToNbt)]` from the `nbt-derive` crate.
+pub trait ToNbt {
+    const TAG: u8;
+
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()>;
+
+    /// Whether a compound field holding this value is left out; true for `None`.
+    fn is_absent(&self) -> bool {
+        false
+    }
+}
+
+/// A type read straight from an [`NbtSource`].
+pub trait FromNbt: Sized {
+    const TAG: u8;
+
+    /// Reads the payload of a tag of type `tag`, which has already been consumed.
+    fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<Self>;
+
+    /// The value of a compound field whose key is absent; `None` makes the key required.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+/// The entries of a compound without its end tag, so that they can be flattened into another.
+pub trait ToNbtCompound {
+    fn write_entries<W: NbtSink>(&self, w: &mut W) -> Result<()>;
+}
+
+/// A compound read one entry at a time, so that it can be flattened into another.
+pub trait FromNbtCompound: Sized {
+    type Builder: Default;
+
+    /// Reads the entry if it belongs to one of this type's fields; returns `false` to leave it
+    /// to the caller, with the payload still unread.
+    fn read_entry<R: NbtSource>(builder: &mut Self::Builder, key: &str, tag: u8, r: &mut R) -> Result<bool>;
+
+    fn build(builder: Self::Builder) -> Result<Self>;
+}
+
+#[inline]
+fn expect_tag(expected: u8, found: u8) -> Result<()> {
+    if expected != found {
+        return Err(Error::TagMismatch { expected, found });
+    }
+    Ok(())
+}
+
+/// Writes one compound entry, or nothing if the value is absent.
+#[inline]
+pub fn write_field<T: ToNbt + ?Sized, W: NbtSink>(w: &mut W, key: &str, value: &T) -> Result<()> {
+    if value.is_absent() {
+        return Ok(());
+    }
+    w.write_u8(T::TAG)?;
+    Nbt::write_nbt_string(w, key)?;
+    value.write_payload(w)
+}
+
+/// Reads a compound payload into `T`, skipping entries none of its fields claim.
+pub fn read_compound<T: FromNbtCompound, R: NbtSource>(r: &mut R, tag: u8) -> Result<T> {
+    expect_tag(10, tag)?;
+    r.push_depth()?;
+    let mut builder = T::Builder::default();
+    loop {
+        let type_id = r.read_u8()?;
+        if type_id == 0 {
+            break;
+        }
+        r.account_tag(type_id)?;
+        r.account(TAG_SIZES[8] + COMPOUND_ENTRY_SIZE)?;
+        let key = Nbt::read_nbt_string(r)?;
+        if !T::read_entry(&mut builder, &key, type_id, r)? {
+            skip(r, type_id)?;
+        }
+    }
+    r.pop_depth();
+    T::build(builder)
+}
+
+/// Reads and discards a payload.
+pub fn skip<R: NbtSource>(r: &mut R, tag: u8) -> Result<()> {
+    match tag {
+        1 => r.read_array::<1>().map(drop),
+        2 => r.read_array::<2>().map(drop),
+        3 | 5 => r.read_array::<4>().map(drop),
+        4 | 6 => r.read_array::<8>().map(drop),
+        7 => Nbt::read_byte_array(r).map(drop),
+        8 => Nbt::read_nbt_string(r).map(drop),
+        9 => Nbt::read_list(r).map(drop),
+        10 => Nbt::read_compound(r).map(drop),
+        11 => Nbt::read_int_array(r).map(drop),
+        12 => Nbt::read_long_array(r).map(drop),
+        x => Err(Error::InvalidTag(x)),
+    }
+}
+
+/// Writes `value` as a root compound, named like [`Nbt`] when `name` is given and nameless
+/// like [`NbtAfter764`](crate::NbtAfter764) otherwise.
+pub fn write_root<T: ToNbt + ?Sized, W: NbtSink>(w: &mut W, name: Option<&str>, value: &T) -> Result<()> {
+    if T::TAG != 10 {
+        return Err(Error::RootTagNotCompound(T::TAG));
+    }
+    w.write_u8(10)?;
+    if let Some(name) = name {
+        Nbt::write_nbt_string(w, name)?;
+    }
+    value.write_payload(w)
+}
+
+/// Reads a root compound, skipping its name if `named`.
+pub fn read_root<T: FromNbt, R: NbtSource>(r: &mut R, named: bool) -> Result<T> {
+    match r.read_u8()? {
+        10 => {
+            r.account_tag(10)?;
+            if named {
+                Nbt::read_nbt_string(r)?;
+            }
+            T::read_payload(r, 10)
+        }
+        x => Err(Error::RootTagNotCompound(x)),
+    }
+}
+
+/// Encodes `value` as a named root with an empty name, like [`Nbt`].
+pub fn to_vec<T: ToNbt + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut w = NbtWriter::new();
+    write_root(&mut w, Some(""), value)?;
+    Ok(w.data)
+}
+
+pub fn from_slice<T: FromNbt>(data: &[u8]) -> Result<T> {
+    read_root(&mut NbtReader::new(data), true)
+}
+
+macro_rules! primitive {
+    ($ty:ty, $tag:expr, $read:ident, $write:ident) => {
+        impl ToNbt for $ty {
+            const TAG: u8 = $tag;
+
+            #[inline]
+            fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+                w.$write(*self as _)
+            }
+        }
+
+        impl FromNbt for $ty {
+            const TAG: u8 = $tag;
+
+            #[inline]
+            fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<$ty> {
+                expect_tag($tag, tag)?;
+                Ok(r.$read()? as $ty)
+            }
+        }
+    };
+}
+
+// Unsigned integers are stored bit-for-bit in the signed tag of the same width, as in `ser`.
+primitive!(i8, 1, read_i8, write_i8);
+primitive!(u8, 1, read_i8, write_i8);
+primitive!(i16, 2, read_i16, write_i16);
+primitive!(u16, 2, read_i16, write_i16);
+primitive!(i32, 3, read_i32, write_i32);
+primitive!(u32, 3, read_i32, write_i32);
+primitive!(i64, 4, read_i64, write_i64);
+primitive!(u64, 4, read_i64, write_i64);
+primitive!(f32, 5, read_f32, write_f32);
+primitive!(f64, 6, read_f64, write_f64);
+
+impl ToNbt for bool {
+    const TAG: u8 = 1;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        w.write_i8(*self as i8)
+    }
+}
+
+impl FromNbt for bool {
+    const TAG: u8 = 1;
+
+    #[inline]
+    fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<bool> {
+        expect_tag(1, tag)?;
+        Ok(r.read_i8()? != 0)
+    }
+}
+
+impl ToNbt for str {
+    const TAG: u8 = 8;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        Nbt::write_nbt_string(w, self)
+    }
+}
+
+impl ToNbt for String {
+    const TAG: u8 = 8;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        Nbt::write_nbt_string(w, self)
+    }
+}
+
+impl FromNbt for String {
+    const TAG: u8 = 8;
+
+    #[inline]
+    fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<String> {
+        expect_tag(8, tag)?;
+        Nbt::read_nbt_string(r)
+    }
+}
+
+impl<T: ToNbt + ?Sized> ToNbt for &T {
+    const TAG: u8 = T::TAG;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        (**self).write_payload(w)
+    }
+
+    fn is_absent(&self) -> bool {
+        (**self).is_absent()
+    }
+}
+
+impl<T: ToNbt> ToNbt for Box<T> {
+    const TAG: u8 = T::TAG;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        (**self).write_payload(w)
+    }
+
+    fn is_absent(&self) -> bool {
+        (**self).is_absent()
+    }
+}
+
+impl<T: FromNbt> FromNbt for Box<T> {
+    const TAG: u8 = T::TAG;
+
+    #[inline]
+    fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<Box<T>> {
+        T::read_payload(r, tag).map(Box::new)
+    }
+
+    fn missing() -> Option<Box<T>> {
+        T::missing().map(Box::new)
+    }
+}
+
+/// `None` fields are left out of the compound; `None` inside a list is an error.
+impl<T: ToNbt> ToNbt for Option<T> {
+    const TAG: u8 = T::TAG;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        match self {
+            Some(v) => v.write_payload(w),
+            None => Err(Error::UnsupportedNone),
+        }
+    }
+
+    fn is_absent(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl<T: FromNbt> FromNbt for Option<T> {
+    const TAG: u8 = T::TAG;
+
+    #[inline]
+    fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<Option<T>> {
+        T::read_payload(r, tag).map(Some)
+    }
+
+    fn missing() -> Option<Option<T>> {
+        Some(None)
+    }
+}
+
+fn write_list<T: ToNbt, W: NbtSink>(w: &mut W, values: &[T]) -> Result<()> {
+    w.write_u8(if values.is_empty() { 0 } else { T::TAG })?;
+    w.write_i32(checked_len(values.len())?)?;
+    for v in values {
+        v.write_payload(w)?;
+    }
+    Ok(())
+}
+
+impl<T: ToNbt> ToNbt for [T] {
+    const TAG: u8 = 9;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        write_list(w, self)
+    }
+}
+
+/// Vectors are lists; wrap them in [`ByteArray`], [`IntArray`] or [`LongArray`] for the array tags.
+impl<T: ToNbt> ToNbt for Vec<T> {
+    const TAG: u8 = 9;
+
+    #[inline]
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        write_list(w, self)
+    }
+}
+
+impl<T: FromNbt> FromNbt for Vec<T> {
+    const TAG: u8 = 9;
+
+    fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<Vec<T>> {
+        expect_tag(9, tag)?;
+        r.push_depth()?;
+        let type_id = r.read_u8()?;
+        let len = r.read_len()?;
+        r.account(4 * len as u64)?;
+        // an empty list may have any element type
+        if len > 0 {
+            expect_tag(T::TAG, type_id)?;
+        }
+        let mut values = Vec::with_capacity(r.capacity_hint(len, min_payload_size::<Nbt>(type_id)));
+        for _ in 0..len {
+            r.account_tag(type_id)?;
+            values.push(T::read_payload(r, type_id)?);
+        }
+        r.pop_depth();
+        Ok(values)
+    }
+}
+
+impl ToNbt for Value {
+    const TAG: u8 = 10;
+
+    /// Only compounds can be written through this impl, as the tag must be known up front.
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+        match self {
+            Value::Compound(entries) => Nbt::write_compound(w, entries),
+            x => Err(Error::TagMismatch { expected: 10, found: x.tag() }),
+        }
+    }
+}
+
+impl FromNbt for Value {
+    const TAG: u8 = 10;
+
+    fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<Value> {
+        expect_tag(10, tag)?;
+        Ok(Value::Compound(Nbt::read_compound(r)?))
+    }
+}
+
+macro_rules! array {
+    ($(#[$doc:meta])* $name:ident, $elem:ty, $tag:expr, $read:ident, $write:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Default, PartialEq)]
+        pub struct $name<T>(pub T);
+
+        impl<T: AsRef<[$elem]>> ToNbt for $name<T> {
+            const TAG: u8 = $tag;
+
+            #[inline]
+            fn write_payload<W: NbtSink>(&self, w: &mut W) -> Result<()> {
+                Nbt::$write(w, self.0.as_ref())
+            }
+        }
+
+        impl<T: From<Vec<$elem>>> FromNbt for $name<T> {
+            const TAG: u8 = $tag;
+
+            #[inline]
+            fn read_payload<R: NbtSource>(r: &mut R, tag: u8) -> Result<$name<T>> {
+                expect_tag($tag, tag)?;
+                Ok($name(Nbt::$read(r)?.into()))
+            }
+        }
+    };
+}
+
+array!(
+    /// Writes a slice of bytes as a byte array tag rather than a list.
+    ByteArray, i8, 7, read_byte_array, write_byte_array
+);
+array!(
+    /// Writes a slice of ints as an int array tag rather than a list.
+    IntArray, i32, 11, read_int_array, write_int_array
+);
+array!(
+    /// Writes a slice of longs as a long array tag rather than a list.
+    LongArray, i64, 12, read_long_array, write_long_array
+);
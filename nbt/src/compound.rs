@@ -0,0 +1,346 @@
+use std::cmp::Ordering;
+use std::collections::{hash_map, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::{Deref, Index};
+
+use crate::{Error, Result, Value};
+
+/// What to do when a compound being read repeats a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Fail with [`Error::DuplicateKey`].
+    Error,
+    /// Keep the first value and skip the rest.
+    FirstWins,
+    /// Keep the last value at the position of the first, like vanilla's `CompoundTag.put`.
+    #[default]
+    LastWins,
+}
+
+/// The entries of a compound tag in insertion order, with a hashed index for lookups.
+///
+/// Dereferences to a slice of entries, so iteration, `len` and `is_empty` come from the slice;
+/// keys can only be changed through the methods here, which keep the index in sync.
+#[derive(Debug, Clone, Default)]
+pub struct Compound {
+    entries: Vec<(String, Value)>,
+    index: HashMap<String, usize>,
+}
+
+impl Compound {
+    pub fn new() -> Compound {
+        Compound::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Compound {
+        Compound {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[(String, Value)] {
+        &self.entries
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.index.get(key).map(|&i| &mut self.entries[i].1)
+    }
+
+    /// The position of `key` in insertion order.
+    #[inline]
+    pub fn get_index_of(&self, key: &str) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
+    #[inline]
+    pub fn get_index(&self, index: usize) -> Option<(&str, &Value)> {
+        self.entries.get(index).map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Replaces the value of an existing key in place, or appends a new entry.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        match self.entry(key) {
+            Entry::Occupied(mut e) => Some(e.insert(value)),
+            Entry::Vacant(e) => {
+                e.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Adds an entry read from data, resolving a repeated key with `policy`.
+    pub fn insert_with(&mut self, key: String, value: Value, policy: DuplicateKeys) -> Result<()> {
+        match self.entry(key) {
+            Entry::Vacant(e) => {
+                e.insert(value);
+            }
+            Entry::Occupied(mut e) => match policy {
+                DuplicateKeys::Error => return Err(Error::DuplicateKey(e.key().to_string())),
+                DuplicateKeys::FirstWins => {}
+                DuplicateKeys::LastWins => {
+                    e.insert(value);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Removes `key`, shifting the later entries down to keep their order.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry(&mut self, key: &str) -> Option<(String, Value)> {
+        let i = self.index.remove(key)?;
+        for index in self.index.values_mut() {
+            if *index > i {
+                *index -= 1;
+            }
+        }
+        Some(self.entries.remove(i))
+    }
+
+    /// Removes `key` in constant time by moving the last entry into its place.
+    pub fn swap_remove(&mut self, key: &str) -> Option<Value> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.swap_remove(i);
+        if let Some((moved, _)) = self.entries.get(i) {
+            *self.index.get_mut(moved.as_str()).expect("indexed key") = i;
+        }
+        Some(value)
+    }
+
+    pub fn entry(&mut self, key: String) -> Entry<'_> {
+        match self.index.entry(key) {
+            hash_map::Entry::Occupied(e) => Entry::Occupied(OccupiedEntry { entry: &mut self.entries[*e.get()] }),
+            hash_map::Entry::Vacant(e) => Entry::Vacant(VacantEntry { index: e, entries: &mut self.entries }),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut Value)> {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| k.as_str())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, &mut Value) -> bool) {
+        self.entries.retain_mut(|(k, v)| keep(k, v));
+        self.reindex();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    fn sorted(&self) -> Vec<(&str, &Value)> {
+        let mut entries: Vec<_> = self.entries.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        entries.sort_unstable_by_key(|&(k, _)| k);
+        entries
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        self.index.extend(self.entries.iter().enumerate().map(|(i, (k, _))| (k.clone(), i)));
+    }
+}
+
+impl Deref for Compound {
+    type Target = [(String, Value)];
+
+    #[inline]
+    fn deref(&self) -> &[(String, Value)] {
+        &self.entries
+    }
+}
+
+impl Index<&str> for Compound {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        self.get(key).unwrap_or_else(|| panic!("no entry for key {:?}", key))
+    }
+}
+
+/// Compounds are equal when they hold the same keys with equal values, in any order.
+impl PartialEq for Compound {
+    fn eq(&self, other: &Compound) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl Eq for Compound {}
+
+/// Independent of entry order: each entry is hashed on its own and the results are summed.
+impl Hash for Compound {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut sum = 0u64;
+        for entry in self.iter() {
+            let mut hasher = DefaultHasher::new();
+            entry.hash(&mut hasher);
+            sum = sum.wrapping_add(hasher.finish());
+        }
+        state.write_usize(self.len());
+        state.write_u64(sum);
+    }
+}
+
+impl PartialOrd for Compound {
+    fn partial_cmp(&self, other: &Compound) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares the entries sorted by key, so that the order agrees with `==`.
+impl Ord for Compound {
+    fn cmp(&self, other: &Compound) -> Ordering {
+        self.sorted().cmp(&other.sorted())
+    }
+}
+
+/// Later duplicates replace earlier ones, as with [`Compound::insert`].
+impl FromIterator<(String, Value)> for Compound {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Compound {
+        let mut compound = Compound::new();
+        compound.extend(iter);
+        compound
+    }
+}
+
+impl Extend<(String, Value)> for Compound {
+    fn extend<I: IntoIterator<Item = (String, Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl From<Vec<(String, Value)>> for Compound {
+    fn from(entries: Vec<(String, Value)>) -> Compound {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<Compound> for Vec<(String, Value)> {
+    fn from(compound: Compound) -> Vec<(String, Value)> {
+        compound.entries
+    }
+}
+
+impl IntoIterator for Compound {
+    type Item = (String, Value);
+    type IntoIter = std::vec::IntoIter<(String, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Compound {
+    type Item = &'a (String, Value);
+    type IntoIter = std::slice::Iter<'a, (String, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+pub enum Entry<'a> {
+    Occupied(OccupiedEntry<'a>),
+    Vacant(VacantEntry<'a>),
+}
+
+pub struct OccupiedEntry<'a> {
+    entry: &'a mut (String, Value),
+}
+
+pub struct VacantEntry<'a> {
+    index: hash_map::VacantEntry<'a, String, usize>,
+    entries: &'a mut Vec<(String, Value)>,
+}
+
+impl<'a> Entry<'a> {
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> Value) -> &'a mut Value {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    pub fn and_modify(mut self, f: impl FnOnce(&mut Value)) -> Entry<'a> {
+        if let Entry::Occupied(e) = &mut self {
+            f(e.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    pub fn key(&self) -> &str {
+        &self.entry.0
+    }
+
+    pub fn get(&self) -> &Value {
+        &self.entry.1
+    }
+
+    pub fn get_mut(&mut self) -> &mut Value {
+        &mut self.entry.1
+    }
+
+    pub fn into_mut(self) -> &'a mut Value {
+        &mut self.entry.1
+    }
+
+    pub fn insert(&mut self, value: Value) -> Value {
+        std::mem::replace(&mut self.entry.1, value)
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    pub fn key(&self) -> &str {
+        self.index.key()
+    }
+
+    /// Appends the entry at the end of the compound.
+    pub fn insert(self, value: Value) -> &'a mut Value {
+        let key = self.index.key().clone();
+        self.index.insert(self.entries.len());
+        self.entries.push((key, value));
+        &mut self.entries.last_mut().expect("just pushed").1
+    }
+}
@@ -0,0 +1,110 @@
+use std::io::{self, BufRead, Read, Write};
+
+use flate2::bufread::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use crate::lz4::{Lz4BlockReader, Lz4BlockWriter};
+use crate::reader::NbtStreamReader;
+use crate::writer::NbtStreamWriter;
+use crate::{Error, NbtReadTrait, NbtWriteTrait, Result, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zlib,
+    Lz4,
+}
+
+impl Compression {
+    /// Guesses the framing from the first bytes: gzip starts with `1f 8b`, zlib with `78`,
+    /// LZ4 with `LZ4Block`, and anything else is taken to be raw NBT.
+    pub fn detect(header: &[u8]) -> Compression {
+        match header {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            [0x78, ..] => Compression::Zlib,
+            [b'L', b'Z', b'4', b'B', b'l', b'o', b'c', b'k', ..] => Compression::Lz4,
+            _ => Compression::None,
+        }
+    }
+}
+
+pub fn write_gzip<D: NbtWriteTrait, W: io::Write>(w: W, value: &Value) -> Result<W> {
+    let mut sink = NbtStreamWriter::new(GzEncoder::new(w, flate2::Compression::default()));
+    D::write_to(&mut sink, value)?;
+    sink.into_inner().finish().map_err(Error::Io)
+}
+
+pub fn write_zlib<D: NbtWriteTrait, W: io::Write>(w: W, value: &Value) -> Result<W> {
+    let mut sink = NbtStreamWriter::new(ZlibEncoder::new(w, flate2::Compression::default()));
+    D::write_to(&mut sink, value)?;
+    sink.into_inner().finish().map_err(Error::Io)
+}
+
+pub fn write_lz4<D: NbtWriteTrait, W: io::Write>(w: W, value: &Value) -> Result<W> {
+    let mut sink = NbtStreamWriter::new(Lz4BlockWriter::new(w));
+    D::write_to(&mut sink, value)?;
+    sink.into_inner().finish().map_err(Error::Io)
+}
+
+pub fn write_compressed<D: NbtWriteTrait, W: io::Write>(w: W, value: &Value, compression: Compression) -> Result<W> {
+    match compression {
+        Compression::None => {
+            let mut sink = NbtStreamWriter::new(w);
+            D::write_to(&mut sink, value)?;
+            sink.finish()
+        }
+        Compression::Gzip => write_gzip::<D, W>(w, value),
+        Compression::Zlib => write_zlib::<D, W>(w, value),
+        Compression::Lz4 => write_lz4::<D, W>(w, value),
+    }
+}
+
+/// Reads one document, detecting gzip, zlib, LZ4 or raw framing from its first bytes.
+pub fn read_compressed<D: NbtReadTrait, R: BufRead>(mut r: R) -> Result<(Value, Compression)> {
+    let compression = Compression::detect(r.fill_buf().map_err(Error::Io)?);
+    Ok((read_decompressed::<D, R>(r, compression)?, compression))
+}
+
+pub fn read_decompressed<D: NbtReadTrait, R: BufRead>(r: R, compression: Compression) -> Result<Value> {
+    match compression {
+        Compression::None => D::from_reader(&mut NbtStreamReader::new(r)),
+        Compression::Gzip => D::from_reader(&mut NbtStreamReader::buffered(GzDecoder::new(r))),
+        Compression::Zlib => D::from_reader(&mut NbtStreamReader::buffered(ZlibDecoder::new(r))),
+        Compression::Lz4 => D::from_reader(&mut NbtStreamReader::buffered(Lz4BlockReader::new(r))),
+    }
+}
+
+/// Compresses an already encoded document.
+pub fn compress(data: &[u8], compression: Compression) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let result = match compression {
+        Compression::None => return Ok(data.to_vec()),
+        Compression::Gzip => {
+            let mut w = GzEncoder::new(&mut out, flate2::Compression::default());
+            w.write_all(data).and_then(|_| w.finish().map(drop))
+        }
+        Compression::Zlib => {
+            let mut w = ZlibEncoder::new(&mut out, flate2::Compression::default());
+            w.write_all(data).and_then(|_| w.finish().map(drop))
+        }
+        Compression::Lz4 => {
+            let mut w = Lz4BlockWriter::new(&mut out);
+            w.write_all(data).and_then(|_| w.finish().map(drop))
+        }
+    };
+    result.map_err(Error::Io)?;
+    Ok(out)
+}
+
+pub fn decompress(data: &[u8], compression: Compression) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let result = match compression {
+        Compression::None => return Ok(data.to_vec()),
+        Compression::Gzip => GzDecoder::new(data).read_to_end(&mut out),
+        Compression::Zlib => ZlibDecoder::new(data).read_to_end(&mut out),
+        Compression::Lz4 => Lz4BlockReader::new(data).read_to_end(&mut out),
+    };
+    result.map_err(Error::Io)?;
+    Ok(out)
+}
@@ -0,0 +1,120 @@
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::forward_to_deserialize_any;
+
+use crate::{Error, Result, Value};
+
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    T::deserialize(value)
+}
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Error {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+macro_rules! deserialize_unsigned {
+    ($method:ident, $visit:ident, $signed:ident, $unsigned:ty) => {
+        // Unsigned integers are stored bit-for-bit in the signed tag of the same width.
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            match self {
+                Value::$signed(v) => visitor.$visit(v as $unsigned),
+                x => x.deserialize_any(visitor),
+            }
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Byte(v) => visitor.visit_i8(v),
+            Value::Short(v) => visitor.visit_i16(v),
+            Value::Int(v) => visitor.visit_i32(v),
+            Value::Long(v) => visitor.visit_i64(v),
+            Value::Float(v) => visitor.visit_f32(v),
+            Value::Double(v) => visitor.visit_f64(v),
+            // elements go through `Value` so that unsigned and bool targets are handled as for tags
+            Value::ByteArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter().map(Value::Byte))),
+            Value::String(v) => visitor.visit_string(v),
+            Value::List(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+            Value::Compound(v) => visitor.visit_map(MapDeserializer::new(v.into_iter())),
+            Value::IntArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter().map(Value::Int))),
+            Value::LongArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter().map(Value::Long))),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Byte(v) => visitor.visit_bool(v != 0),
+            x => x.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_unsigned!(deserialize_u8, visit_u8, Byte, u8);
+    deserialize_unsigned!(deserialize_u16, visit_u16, Short, u16);
+    deserialize_unsigned!(deserialize_u32, visit_u32, Int, u32);
+    deserialize_unsigned!(deserialize_u64, visit_u64, Long, u64);
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::ByteArray(v) => visitor.visit_byte_buf(v.into_iter().map(|b| b as u8).collect()),
+            x => x.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::String(v) => visitor.visit_enum(v.into_deserializer()),
+            Value::Compound(v) if v.len() == 1 => {
+                visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(v.into_iter())))
+            }
+            x => Err(Error::Custom(format!("expected a string or a single entry compound for an enum, got tag {}", x.tag()))),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier
+    }
+}
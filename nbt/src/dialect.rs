@@ -0,0 +1,630 @@
+use std::borrow::Cow;
+
+use crate::bedrock::{BedrockNbt, BedrockNetworkNbt};
+use crate::reader::{NbtReader, NbtSource, COMPOUND_ENTRY_SIZE, TAG_SIZES};
+use crate::value_ref::{self, ValueRef};
+use crate::writer::{checked_len, NbtSink};
+use crate::{mutf8, Compound, Error, NamedTag, NbtReadTrait, NbtWriteTrait, Result, Value};
+
+/// How a dialect frames numbers and lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Numbers {
+    BigEndian,
+    LittleEndian,
+    /// Ints and longs, including lengths, are ZigZag VarInts, string lengths are unsigned
+    /// VarInts and everything else is little-endian.
+    VarInt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strings {
+    /// Java's modified UTF-8, see [`mutf8`].
+    Mutf8,
+    Utf8,
+}
+
+/// What sets one NBT dialect apart from another; the tag layout is shared, and every dialect is
+/// read and written by the same code, specialised on these constants.
+///
+/// [`NbtReadTrait`] and [`NbtWriteTrait`] are implemented for every dialect.
+pub trait Dialect {
+    /// Whether the root compound is preceded by a name.
+    const NAMED_ROOT: bool;
+    const NUMBERS: Numbers;
+    const STRINGS: Strings;
+}
+
+/// Java Edition NBT, as used in files and, before protocol 764, on the network.
+#[derive(Default)]
+pub struct Nbt;
+
+impl Nbt {
+    pub fn new() -> Nbt {
+        Nbt
+    }
+}
+
+/// Java Edition network NBT since protocol 764 (1.20.2), where the root compound has no name.
+#[derive(Default)]
+pub struct NbtAfter764;
+
+impl NbtAfter764 {
+    pub fn new() -> NbtAfter764 {
+        NbtAfter764
+    }
+}
+
+impl Dialect for Nbt {
+    const NAMED_ROOT: bool = true;
+    const NUMBERS: Numbers = Numbers::BigEndian;
+    const STRINGS: Strings = Strings::Mutf8;
+}
+
+impl Dialect for NbtAfter764 {
+    const NAMED_ROOT: bool = false;
+    const NUMBERS: Numbers = Numbers::BigEndian;
+    const STRINGS: Strings = Strings::Mutf8;
+}
+
+impl Dialect for BedrockNbt {
+    const NAMED_ROOT: bool = true;
+    const NUMBERS: Numbers = Numbers::LittleEndian;
+    const STRINGS: Strings = Strings::Utf8;
+}
+
+impl Dialect for BedrockNetworkNbt {
+    const NAMED_ROOT: bool = true;
+    const NUMBERS: Numbers = Numbers::VarInt;
+    const STRINGS: Strings = Strings::Utf8;
+}
+
+/// A dialect chosen at runtime, for example from the protocol version of a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DialectKind {
+    #[default]
+    Nbt,
+    NbtAfter764,
+    BedrockNbt,
+    BedrockNetworkNbt,
+}
+
+// Runs `$body` with `$d` naming the dialect type of `$kind`.
+macro_rules! dispatch {
+    ($kind:expr, $d:ident => $body:expr) => {
+        match $kind {
+            DialectKind::Nbt => {
+                type $d = Nbt;
+                $body
+            }
+            DialectKind::NbtAfter764 => {
+                type $d = NbtAfter764;
+                $body
+            }
+            DialectKind::BedrockNbt => {
+                type $d = BedrockNbt;
+                $body
+            }
+            DialectKind::BedrockNetworkNbt => {
+                type $d = BedrockNetworkNbt;
+                $body
+            }
+        }
+    };
+}
+
+impl DialectKind {
+    /// The first Java Edition protocol version whose network NBT has an unnamed root.
+    pub const UNNAMED_ROOT_PROTOCOL: i32 = 764;
+
+    /// The dialect of Java Edition network NBT for `protocol`.
+    pub fn for_protocol(protocol: i32) -> DialectKind {
+        if protocol >= Self::UNNAMED_ROOT_PROTOCOL {
+            DialectKind::NbtAfter764
+        } else {
+            DialectKind::Nbt
+        }
+    }
+
+    pub fn named_root(self) -> bool {
+        dispatch!(self, D => D::NAMED_ROOT)
+    }
+
+    pub fn numbers(self) -> Numbers {
+        dispatch!(self, D => D::NUMBERS)
+    }
+
+    pub fn strings(self) -> Strings {
+        dispatch!(self, D => D::STRINGS)
+    }
+
+    pub fn read_named<R: NbtSource>(self, r: &mut R) -> Result<NamedTag> {
+        dispatch!(self, D => read_root::<D, R>(r))
+    }
+
+    /// Reads the root compound, dropping its name.
+    pub fn read<R: NbtSource>(self, r: &mut R) -> Result<Value> {
+        Ok(self.read_named(r)?.value)
+    }
+
+    /// Writes `value` as the root with an empty name, if the dialect names it at all.
+    pub fn write<W: NbtSink>(self, w: &mut W, value: &Value) -> Result<()> {
+        dispatch!(self, D => write_root::<D, W>(w, "", value))
+    }
+
+    pub fn write_named<W: NbtSink>(self, w: &mut W, tag: &NamedTag) -> Result<()> {
+        dispatch!(self, D => write_root::<D, W>(w, &tag.name, &tag.value))
+    }
+}
+
+#[inline]
+fn zigzag32(v: u32) -> i32 {
+    (v >> 1) as i32 ^ -((v & 1) as i32)
+}
+
+#[inline]
+fn zigzag64(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+fn read_var_u64<R: NbtSource>(r: &mut R, max_bytes: u32) -> Result<u64> {
+    let mut value = 0u64;
+    for i in 0..max_bytes {
+        let b = r.read_u8()?;
+        value |= ((b & 0x7f) as u64) << (7 * i);
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::VarIntTooLong(max_bytes as usize))
+}
+
+fn write_var_u64<W: NbtSink>(w: &mut W, mut value: u64) -> Result<()> {
+    let mut buf = [0u8; 10];
+    let mut n = 0;
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[n] = b;
+            n += 1;
+            break;
+        }
+        buf[n] = b | 0x80;
+        n += 1;
+    }
+    w.write_bytes(&buf[..n])
+}
+
+#[inline]
+fn read_i16<D: Dialect, R: NbtSource>(r: &mut R) -> Result<i16> {
+    match D::NUMBERS {
+        Numbers::BigEndian => r.read_i16(),
+        Numbers::LittleEndian | Numbers::VarInt => Ok(i16::from_le_bytes(r.read_array()?)),
+    }
+}
+
+#[inline]
+fn read_i32<D: Dialect, R: NbtSource>(r: &mut R) -> Result<i32> {
+    match D::NUMBERS {
+        Numbers::BigEndian => r.read_i32(),
+        Numbers::LittleEndian => Ok(i32::from_le_bytes(r.read_array()?)),
+        Numbers::VarInt => Ok(zigzag32(read_var_u64(r, 5)? as u32)),
+    }
+}
+
+#[inline]
+fn read_i64<D: Dialect, R: NbtSource>(r: &mut R) -> Result<i64> {
+    match D::NUMBERS {
+        Numbers::BigEndian => r.read_i64(),
+        Numbers::LittleEndian => Ok(i64::from_le_bytes(r.read_array()?)),
+        Numbers::VarInt => Ok(zigzag64(read_var_u64(r, 10)?)),
+    }
+}
+
+#[inline]
+fn read_f32<D: Dialect, R: NbtSource>(r: &mut R) -> Result<f32> {
+    match D::NUMBERS {
+        Numbers::BigEndian => r.read_f32(),
+        Numbers::LittleEndian | Numbers::VarInt => Ok(f32::from_le_bytes(r.read_array()?)),
+    }
+}
+
+#[inline]
+fn read_f64<D: Dialect, R: NbtSource>(r: &mut R) -> Result<f64> {
+    match D::NUMBERS {
+        Numbers::BigEndian => r.read_f64(),
+        Numbers::LittleEndian | Numbers::VarInt => Ok(f64::from_le_bytes(r.read_array()?)),
+    }
+}
+
+#[inline]
+fn read_len<D: Dialect, R: NbtSource>(r: &mut R) -> Result<usize> {
+    let len = read_i32::<D, R>(r)?;
+    r.accounter().check_len(len)
+}
+
+// The longest encoded string a dialect allows. Bedrock's network VarInt lengths could say more,
+// but the game and the other implementations refuse anything past i16::MAX.
+fn max_string_len<D: Dialect>() -> usize {
+    match D::NUMBERS {
+        Numbers::BigEndian | Numbers::LittleEndian => u16::MAX as usize,
+        Numbers::VarInt => i16::MAX as usize,
+    }
+}
+
+#[inline]
+fn read_string_len<D: Dialect, R: NbtSource>(r: &mut R) -> Result<usize> {
+    match D::NUMBERS {
+        Numbers::BigEndian => Ok(r.read_u16()? as usize),
+        Numbers::LittleEndian => Ok(u16::from_le_bytes(r.read_array()?) as usize),
+        Numbers::VarInt => {
+            let len = read_var_u64(r, 5)? as u32 as usize;
+            let max = max_string_len::<D>();
+            if len > max {
+                return Err(Error::StringTooLong { len, max });
+            }
+            Ok(len)
+        }
+    }
+}
+
+#[inline]
+fn write_i16<D: Dialect, W: NbtSink>(w: &mut W, value: i16) -> Result<()> {
+    match D::NUMBERS {
+        Numbers::BigEndian => w.write_i16(value),
+        Numbers::LittleEndian | Numbers::VarInt => w.write_bytes(&value.to_le_bytes()),
+    }
+}
+
+#[inline]
+fn write_i32<D: Dialect, W: NbtSink>(w: &mut W, value: i32) -> Result<()> {
+    match D::NUMBERS {
+        Numbers::BigEndian => w.write_i32(value),
+        Numbers::LittleEndian => w.write_bytes(&value.to_le_bytes()),
+        Numbers::VarInt => write_var_u64(w, ((value << 1) ^ (value >> 31)) as u32 as u64),
+    }
+}
+
+#[inline]
+fn write_i64<D: Dialect, W: NbtSink>(w: &mut W, value: i64) -> Result<()> {
+    match D::NUMBERS {
+        Numbers::BigEndian => w.write_i64(value),
+        Numbers::LittleEndian => w.write_bytes(&value.to_le_bytes()),
+        Numbers::VarInt => write_var_u64(w, ((value << 1) ^ (value >> 63)) as u64),
+    }
+}
+
+#[inline]
+fn write_f32<D: Dialect, W: NbtSink>(w: &mut W, value: f32) -> Result<()> {
+    match D::NUMBERS {
+        Numbers::BigEndian => w.write_f32(value),
+        Numbers::LittleEndian | Numbers::VarInt => w.write_bytes(&value.to_le_bytes()),
+    }
+}
+
+#[inline]
+fn write_f64<D: Dialect, W: NbtSink>(w: &mut W, value: f64) -> Result<()> {
+    match D::NUMBERS {
+        Numbers::BigEndian => w.write_f64(value),
+        Numbers::LittleEndian | Numbers::VarInt => w.write_bytes(&value.to_le_bytes()),
+    }
+}
+
+#[inline]
+fn write_string_len<D: Dialect, W: NbtSink>(w: &mut W, len: usize) -> Result<()> {
+    match D::NUMBERS {
+        Numbers::BigEndian => w.write_u16(len as u16),
+        Numbers::LittleEndian => w.write_bytes(&(len as u16).to_le_bytes()),
+        Numbers::VarInt => write_var_u64(w, len as u64),
+    }
+}
+
+fn read_string<D: Dialect, R: NbtSource>(r: &mut R) -> Result<String> {
+    let len = read_string_len::<D, R>(r)?;
+    r.account(2 * len as u64)?;
+    match D::STRINGS {
+        Strings::Mutf8 => r.read_string(len),
+        Strings::Utf8 => String::from_utf8(r.read_bytes(len)?.into_owned()).map_err(Error::Utf8),
+    }
+}
+
+fn read_byte_array<D: Dialect, R: NbtSource>(r: &mut R) -> Result<Vec<i8>> {
+    let len = read_len::<D, R>(r)?;
+    r.account(len as u64)?;
+    r.read_i8_array(len)
+}
+
+fn read_int_array<D: Dialect, R: NbtSource>(r: &mut R) -> Result<Vec<i32>> {
+    let len = read_len::<D, R>(r)?;
+    r.account(4 * len as u64)?;
+    if D::NUMBERS == Numbers::BigEndian {
+        return r.read_i32_array(len);
+    }
+    let mut value = Vec::with_capacity(r.capacity_hint(len, 4));
+    for _ in 0..len {
+        value.push(read_i32::<D, R>(r)?);
+    }
+    Ok(value)
+}
+
+fn read_long_array<D: Dialect, R: NbtSource>(r: &mut R) -> Result<Vec<i64>> {
+    let len = read_len::<D, R>(r)?;
+    r.account(8 * len as u64)?;
+    if D::NUMBERS == Numbers::BigEndian {
+        return r.read_i64_array(len);
+    }
+    let mut value = Vec::with_capacity(r.capacity_hint(len, 8));
+    for _ in 0..len {
+        value.push(read_i64::<D, R>(r)?);
+    }
+    Ok(value)
+}
+
+// The fewest bytes a payload of `type_id` takes, so that a list can't reserve more elements than
+// the rest of the input could hold; 0 for tags no element can have.
+pub(crate) fn min_payload_size<D: Dialect>(type_id: u8) -> usize {
+    let var_int = D::NUMBERS == Numbers::VarInt;
+    match type_id {
+        1 => 1,
+        2 => 2,
+        3 | 4 if var_int => 1,
+        3 | 5 => 4,
+        4 | 6 => 8,
+        7 | 11 | 12 if var_int => 1,
+        7 | 11 | 12 => 4,
+        8 if var_int => 1,
+        8 => 2,
+        9 if var_int => 2,
+        9 => 5,
+        10 => 1,
+        _ => 0,
+    }
+}
+
+// Expanded in both `read_list` and `read_compound` so that each nesting level costs one stack frame.
+macro_rules! read_payload {
+    ($d:ty, $r:expr, $type_id:expr) => {
+        match $type_id {
+            1 => Value::Byte($r.read_i8()?),
+            2 => Value::Short(read_i16::<$d, R>($r)?),
+            3 => Value::Int(read_i32::<$d, R>($r)?),
+            4 => Value::Long(read_i64::<$d, R>($r)?),
+            5 => Value::Float(read_f32::<$d, R>($r)?),
+            6 => Value::Double(read_f64::<$d, R>($r)?),
+            7 => Value::ByteArray(read_byte_array::<$d, R>($r)?),
+            8 => Value::String(read_string::<$d, R>($r)?),
+            9 => Value::List(read_list::<$d, R>($r)?),
+            10 => Value::Compound(read_compound::<$d, R>($r)?),
+            11 => Value::IntArray(read_int_array::<$d, R>($r)?),
+            12 => Value::LongArray(read_long_array::<$d, R>($r)?),
+            x => return Err(Error::InvalidTag(x)),
+        }
+    };
+}
+
+fn read_list<D: Dialect, R: NbtSource>(r: &mut R) -> Result<Vec<Value>> {
+    r.push_depth()?;
+    let type_id = r.read_u8()?;
+    let len = read_len::<D, R>(r)?;
+    r.account(4 * len as u64)?;
+    // an empty list may have any element type
+    let size = min_payload_size::<D>(type_id);
+    if len > 0 && size == 0 {
+        return Err(Error::InvalidTag(type_id));
+    }
+    let mut list = Vec::with_capacity(r.capacity_hint(len, size));
+    for _ in 0..len {
+        r.account_tag(type_id)?;
+        list.push(read_payload!(D, r, type_id));
+    }
+    r.pop_depth();
+    Ok(list)
+}
+
+fn read_compound<D: Dialect, R: NbtSource>(r: &mut R) -> Result<Compound> {
+    r.push_depth()?;
+    let policy = r.accounter().limits.duplicate_keys;
+    let mut compound = Compound::new();
+    loop {
+        let type_id = r.read_u8()?;
+        if type_id == 0 {
+            break;
+        }
+        r.account_tag(type_id)?;
+        r.account(TAG_SIZES[8] + COMPOUND_ENTRY_SIZE)?;
+        let name = read_string::<D, R>(r)?;
+        let value = read_payload!(D, r, type_id);
+        compound.insert_with(name, value, policy)?;
+    }
+    r.pop_depth();
+    Ok(compound)
+}
+
+fn read_root<D: Dialect, R: NbtSource>(r: &mut R) -> Result<NamedTag> {
+    match r.read_u8()? {
+        10 => {
+            r.account_tag(10)?;
+            let name = if D::NAMED_ROOT { read_string::<D, R>(r)? } else { String::new() };
+            Ok(NamedTag::new(name, Value::Compound(read_compound::<D, R>(r)?)))
+        }
+        x => Err(Error::RootTagNotCompound(x)),
+    }
+}
+
+fn write_string<D: Dialect, W: NbtSink>(w: &mut W, value: &str) -> Result<()> {
+    let bytes = match D::STRINGS {
+        Strings::Mutf8 => mutf8::encode(value),
+        Strings::Utf8 => Cow::Borrowed(value.as_bytes()),
+    };
+    let max = max_string_len::<D>();
+    if bytes.len() > max {
+        return Err(Error::StringTooLong { len: bytes.len(), max });
+    }
+    write_string_len::<D, W>(w, bytes.len())?;
+    w.write_bytes(&bytes)
+}
+
+fn write_byte_array<D: Dialect, W: NbtSink>(w: &mut W, value: &[i8]) -> Result<()> {
+    write_i32::<D, W>(w, checked_len(value.len())?)?;
+    let bytes: Vec<u8> = value.iter().map(|&b| b as u8).collect();
+    w.write_bytes(&bytes)
+}
+
+fn write_int_array<D: Dialect, W: NbtSink>(w: &mut W, value: &[i32]) -> Result<()> {
+    write_i32::<D, W>(w, checked_len(value.len())?)?;
+    if D::NUMBERS == Numbers::BigEndian {
+        return w.write_i32_array(value);
+    }
+    for &v in value {
+        write_i32::<D, W>(w, v)?;
+    }
+    Ok(())
+}
+
+fn write_long_array<D: Dialect, W: NbtSink>(w: &mut W, value: &[i64]) -> Result<()> {
+    write_i32::<D, W>(w, checked_len(value.len())?)?;
+    if D::NUMBERS == Numbers::BigEndian {
+        return w.write_i64_array(value);
+    }
+    for &v in value {
+        write_i64::<D, W>(w, v)?;
+    }
+    Ok(())
+}
+
+fn write_payload<D: Dialect, W: NbtSink>(w: &mut W, value: &Value) -> Result<()> {
+    match value {
+        Value::Byte(v) => w.write_i8(*v),
+        Value::Short(v) => write_i16::<D, W>(w, *v),
+        Value::Int(v) => write_i32::<D, W>(w, *v),
+        Value::Long(v) => write_i64::<D, W>(w, *v),
+        Value::Float(v) => write_f32::<D, W>(w, *v),
+        Value::Double(v) => write_f64::<D, W>(w, *v),
+        Value::ByteArray(v) => write_byte_array::<D, W>(w, v),
+        Value::String(v) => write_string::<D, W>(w, v),
+        Value::List(v) => write_list::<D, W>(w, v),
+        Value::Compound(v) => write_compound::<D, W>(w, v),
+        Value::IntArray(v) => write_int_array::<D, W>(w, v),
+        Value::LongArray(v) => write_long_array::<D, W>(w, v),
+    }
+}
+
+fn write_list<D: Dialect, W: NbtSink>(w: &mut W, value: &[Value]) -> Result<()> {
+    let tag = value.first().map_or(0, Value::tag);
+    if !value.iter().all(|v| v.tag() == tag) {
+        return Err(Error::ListTypeNotSame);
+    }
+    w.write_u8(tag)?;
+    write_i32::<D, W>(w, checked_len(value.len())?)?;
+    for v in value {
+        write_payload::<D, W>(w, v)?;
+    }
+    Ok(())
+}
+
+fn write_compound<D: Dialect, W: NbtSink>(w: &mut W, value: &[(String, Value)]) -> Result<()> {
+    for (key, v) in value {
+        w.write_u8(v.tag())?;
+        write_string::<D, W>(w, key)?;
+        write_payload::<D, W>(w, v)?;
+    }
+    w.write_u8(0)
+}
+
+fn write_root<D: Dialect, W: NbtSink>(w: &mut W, name: &str, value: &Value) -> Result<()> {
+    match value {
+        Value::Compound(entries) => {
+            w.write_u8(10)?;
+            if D::NAMED_ROOT {
+                write_string::<D, W>(w, name)?;
+            }
+            write_compound::<D, W>(w, entries)
+        }
+        x => Err(Error::RootTagNotCompound(x.tag())),
+    }
+}
+
+impl<D: Dialect> NbtReadTrait for D {
+    #[inline]
+    fn read_byte_array<R: NbtSource>(r: &mut R) -> Result<Vec<i8>> {
+        read_byte_array::<D, R>(r)
+    }
+
+    #[inline]
+    fn read_nbt_string<R: NbtSource>(r: &mut R) -> Result<String> {
+        read_string::<D, R>(r)
+    }
+
+    #[inline]
+    fn read_int_array<R: NbtSource>(r: &mut R) -> Result<Vec<i32>> {
+        read_int_array::<D, R>(r)
+    }
+
+    #[inline]
+    fn read_long_array<R: NbtSource>(r: &mut R) -> Result<Vec<i64>> {
+        read_long_array::<D, R>(r)
+    }
+
+    #[inline]
+    fn read_list<R: NbtSource>(r: &mut R) -> Result<Vec<Value>> {
+        read_list::<D, R>(r)
+    }
+
+    #[inline]
+    fn read_compound<R: NbtSource>(r: &mut R) -> Result<Compound> {
+        read_compound::<D, R>(r)
+    }
+
+    fn from_reader_named<R: NbtSource>(r: &mut R) -> Result<NamedTag> {
+        read_root::<D, R>(r)
+    }
+
+    fn from_reader_ref<'a>(r: &mut NbtReader<'a>) -> Result<ValueRef<'a>> {
+        match (D::NUMBERS, D::STRINGS) {
+            (Numbers::BigEndian, Strings::Mutf8) => value_ref::read_root(r, D::NAMED_ROOT),
+            _ => Err(Error::Unsupported("ValueRef only reads big-endian NBT")),
+        }
+    }
+}
+
+impl<D: Dialect> NbtWriteTrait for D {
+    #[inline]
+    fn write_byte_array<W: NbtSink>(w: &mut W, value: &[i8]) -> Result<()> {
+        write_byte_array::<D, W>(w, value)
+    }
+
+    #[inline]
+    fn write_nbt_string<W: NbtSink>(w: &mut W, value: &str) -> Result<()> {
+        write_string::<D, W>(w, value)
+    }
+
+    #[inline]
+    fn write_int_array<W: NbtSink>(w: &mut W, value: &[i32]) -> Result<()> {
+        write_int_array::<D, W>(w, value)
+    }
+
+    #[inline]
+    fn write_long_array<W: NbtSink>(w: &mut W, value: &[i64]) -> Result<()> {
+        write_long_array::<D, W>(w, value)
+    }
+
+    #[inline]
+    fn write_list<W: NbtSink>(w: &mut W, value: &[Value]) -> Result<()> {
+        write_list::<D, W>(w, value)
+    }
+
+    #[inline]
+    fn write_compound<W: NbtSink>(w: &mut W, value: &[(String, Value)]) -> Result<()> {
+        write_compound::<D, W>(w, value)
+    }
+
+    /// Writes the root with an empty name, as vanilla does for files; dialects without root
+    /// names write none.
+    fn write_to<W: NbtSink>(w: &mut W, value: &Value) -> Result<()> {
+        write_root::<D, W>(w, "", value)
+    }
+
+    fn write_to_with_name<W: NbtSink>(w: &mut W, name: &str, value: &Value) -> Result<()> {
+        write_root::<D, W>(w, name, value)
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use crate::{snbt, Compound, Error, NbtPath, Result, Value};
+
+/// One difference between two trees, located by the path of the tag that differs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added { path: NbtPath, value: Value },
+    Removed { path: NbtPath, value: Value },
+    Changed { path: NbtPath, old: Value, new: Value },
+}
+
+impl Change {
+    pub fn path(&self) -> &NbtPath {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// The changes that turn one tree into another, as produced by [`diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    pub changes: Vec<Change>,
+}
+
+/// Compares two trees. Compounds are compared key by key and lists of the same length and
+/// element type index by index; anything else that differs is reported as a whole.
+pub fn diff(old: &Value, new: &Value) -> Patch {
+    let mut changes = Vec::new();
+    diff_into(&mut NbtPath::root(), old, new, &mut changes);
+    Patch { changes }
+}
+
+fn diff_into(path: &mut NbtPath, old: &Value, new: &Value, out: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Compound(old), Value::Compound(new)) => {
+            for (key, old_value) in old.iter() {
+                let mut child = path.clone();
+                child.push_key(key);
+                match new.get(key) {
+                    Some(new_value) => diff_into(&mut child, old_value, new_value, out),
+                    None => out.push(Change::Removed { path: child, value: old_value.clone() }),
+                }
+            }
+            for (key, new_value) in new.iter().filter(|(k, _)| !old.contains_key(k)) {
+                let mut child = path.clone();
+                child.push_key(key);
+                out.push(Change::Added { path: child, value: new_value.clone() });
+            }
+        }
+        (Value::List(a), Value::List(b))
+            if a.len() == b.len() && a.first().map(Value::tag) == b.first().map(Value::tag) =>
+        {
+            for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                let mut child = path.clone();
+                child.push_index(i as i32);
+                diff_into(&mut child, a, b, out);
+            }
+        }
+        (old, new) if old != new => {
+            out.push(Change::Changed { path: path.clone(), old: old.clone(), new: new.clone() });
+        }
+        _ => {}
+    }
+}
+
+/// Merges `source` into `target` like `/data merge`: compounds are merged key by key, and every
+/// other tag, lists included, replaces what was there.
+pub fn merge(target: &mut Value, source: &Value) {
+    match (target, source) {
+        (Value::Compound(target), Value::Compound(source)) => merge_compound(target, source),
+        (target, source) => *target = source.clone(),
+    }
+}
+
+fn merge_compound(target: &mut Compound, source: &Compound) {
+    for (key, value) in source.iter() {
+        match (target.get_mut(key), value) {
+            (Some(Value::Compound(target)), Value::Compound(source)) => merge_compound(target, source),
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn conflict(path: &NbtPath, message: &str) -> Error {
+    Error::PatchConflict { path: path.to_string(), message: message.to_string() }
+}
+
+impl Patch {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Applies the changes in order. Each change is checked against the current tree first, so a
+    /// patch only applies to the tree it was made from; on error the tree is left partly patched.
+    pub fn apply(&self, root: &mut Value) -> Result<()> {
+        for change in &self.changes {
+            match change {
+                Change::Added { path, value } => {
+                    if !path.get(root).is_empty() {
+                        return Err(conflict(path, "already exists"));
+                    }
+                    if path.set(root, value.clone()) == 0 {
+                        return Err(conflict(path, "cannot be added"));
+                    }
+                }
+                Change::Removed { path, value } => {
+                    if path.get(root) != [value] {
+                        return Err(conflict(path, "does not hold the removed value"));
+                    }
+                    path.remove(root);
+                }
+                Change::Changed { path, old, new } => {
+                    if path.get(root) != [old] {
+                        return Err(conflict(path, "does not hold the old value"));
+                    }
+                    path.set(root, new.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The patch that undoes this one.
+    pub fn reverse(&self) -> Patch {
+        let changes = self.changes.iter().rev().map(|change| match change.clone() {
+            Change::Added { path, value } => Change::Removed { path, value },
+            Change::Removed { path, value } => Change::Added { path, value },
+            Change::Changed { path, old, new } => Change::Changed { path, old: new, new: old },
+        });
+        Patch { changes: changes.collect() }
+    }
+
+    /// Encodes the patch as a list of compounds such as `{op:"change",path:"a.b",old:1,new:2}`,
+    /// so it can be stored with any dialect or as SNBT. The root path is an empty string.
+    pub fn to_value(&self) -> Value {
+        let entry = |op: &str, path: &NbtPath, values: &[(&str, &Value)]| {
+            let mut compound = Compound::new();
+            compound.insert("op".to_string(), Value::String(op.to_string()));
+            compound.insert("path".to_string(), Value::String(path.to_string()));
+            for &(key, value) in values {
+                compound.insert(key.to_string(), value.clone());
+            }
+            Value::Compound(compound)
+        };
+        Value::List(
+            self.changes
+                .iter()
+                .map(|change| match change {
+                    Change::Added { path, value } => entry("add", path, &[("value", value)]),
+                    Change::Removed { path, value } => entry("remove", path, &[("value", value)]),
+                    Change::Changed { path, old, new } => entry("change", path, &[("old", old), ("new", new)]),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn from_value(value: &Value) -> Result<Patch> {
+        let invalid = |message: &str| Error::Custom(format!("invalid patch: {}", message));
+        let entries = value.as_list().ok_or_else(|| invalid("expected a list"))?;
+        let mut changes = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let path = match entry.get("path").and_then(Value::as_str) {
+                Some("") => NbtPath::root(),
+                Some(path) => NbtPath::parse(path)?,
+                None => return Err(invalid("missing path")),
+            };
+            let field = |key: &str| entry.get(key).cloned().ok_or_else(|| invalid(&format!("missing {}", key)));
+            changes.push(match entry.get("op").and_then(Value::as_str) {
+                Some("add") => Change::Added { path, value: field("value")? },
+                Some("remove") => Change::Removed { path, value: field("value")? },
+                Some("change") => Change::Changed { path, old: field("old")?, new: field("new")? },
+                _ => return Err(invalid("unknown op")),
+            });
+        }
+        Ok(Patch { changes })
+    }
+}
+
+/// One line per change: `+ path: value`, `- path: value` or `~ path: old -> new`.
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            match change {
+                Change::Added { path, value } => writeln!(f, "+ {}: {}", path, snbt::to_string(value))?,
+                Change::Removed { path, value } => writeln!(f, "- {}: {}", path, snbt::to_string(value))?,
+                Change::Changed { path, old, new } => {
+                    writeln!(f, "~ {}: {} -> {}", path, snbt::to_string(old), snbt::to_string(new))?
+                }
+            }
+        }
+        Ok(())
+    }
+}
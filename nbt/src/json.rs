@@ -0,0 +1,136 @@
+use serde_json::{json, Map, Number, Value as Json};
+
+use crate::{Compound, Error, Result, Value};
+
+/// Encodes `value` with every tag as `{"type":"int","value":1}`, so that [`from_typed`] gives back
+/// exactly the same tree. Compound entries keep their order. Floats that JSON cannot represent
+/// are stored by their bits, as `{"type":"float","bits":2143289344}`.
+pub fn to_typed(value: &Value) -> Json {
+    let (kind, value) = match value {
+        Value::Byte(v) => ("byte", json!(v)),
+        Value::Short(v) => ("short", json!(v)),
+        Value::Int(v) => ("int", json!(v)),
+        Value::Long(v) => ("long", json!(v)),
+        Value::Float(v) if v.is_finite() => ("float", json!(v)),
+        Value::Float(v) => return json!({ "type": "float", "bits": v.to_bits() }),
+        Value::Double(v) if v.is_finite() => ("double", json!(v)),
+        Value::Double(v) => return json!({ "type": "double", "bits": v.to_bits() }),
+        Value::ByteArray(v) => ("byte_array", json!(v)),
+        Value::String(v) => ("string", json!(v)),
+        Value::List(v) => ("list", Json::Array(v.iter().map(to_typed).collect())),
+        Value::Compound(v) => ("compound", Json::Object(v.iter().map(|(k, v)| (k.clone(), to_typed(v))).collect())),
+        Value::IntArray(v) => ("int_array", json!(v)),
+        Value::LongArray(v) => ("long_array", json!(v)),
+    };
+    json!({ "type": kind, "value": value })
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::Json(message.into())
+}
+
+fn integer<T: TryFrom<i64>>(json: &Json) -> Result<T> {
+    json.as_i64()
+        .and_then(|v| T::try_from(v).ok())
+        .ok_or_else(|| invalid(format!("{} is not a valid {}", json, std::any::type_name::<T>())))
+}
+
+fn number(json: &Json) -> Result<f64> {
+    json.as_f64().ok_or_else(|| invalid(format!("{} is not a number", json)))
+}
+
+fn array<T>(json: &Json, element: impl Fn(&Json) -> Result<T>) -> Result<Vec<T>> {
+    let values = json.as_array().ok_or_else(|| invalid(format!("{} is not an array", json)))?;
+    values.iter().map(element).collect()
+}
+
+/// Decodes the output of [`to_typed`].
+pub fn from_typed(json: &Json) -> Result<Value> {
+    let object = json.as_object().ok_or_else(|| invalid(format!("{} is not a typed tag", json)))?;
+    let kind = object.get("type").and_then(Json::as_str).ok_or_else(|| invalid("missing tag type"))?;
+    if let Some(bits) = object.get("bits") {
+        return match kind {
+            "float" => Ok(Value::Float(f32::from_bits(integer(bits)?))),
+            "double" => Ok(Value::Double(f64::from_bits(bits.as_u64().ok_or_else(|| invalid("invalid double bits"))?))),
+            _ => Err(invalid(format!("{} has no bits", kind))),
+        };
+    }
+    let value = object.get("value").ok_or_else(|| invalid("missing tag value"))?;
+    Ok(match kind {
+        "byte" => Value::Byte(integer(value)?),
+        "short" => Value::Short(integer(value)?),
+        "int" => Value::Int(integer(value)?),
+        "long" => Value::Long(integer(value)?),
+        "float" => Value::Float(number(value)? as f32),
+        "double" => Value::Double(number(value)?),
+        "byte_array" => Value::ByteArray(array(value, integer)?),
+        "string" => Value::String(value.as_str().ok_or_else(|| invalid(format!("{} is not a string", value)))?.to_string()),
+        "list" => {
+            let list = array(value, from_typed)?;
+            if list.windows(2).any(|w| w[0].tag() != w[1].tag()) {
+                return Err(Error::ListTypeNotSame);
+            }
+            Value::List(list)
+        }
+        "compound" => {
+            let entries = value.as_object().ok_or_else(|| invalid(format!("{} is not an object", value)))?;
+            let mut compound = Compound::with_capacity(entries.len());
+            for (key, value) in entries {
+                compound.insert(key.clone(), from_typed(value)?);
+            }
+            Value::Compound(compound)
+        }
+        "int_array" => Value::IntArray(array(value, integer)?),
+        "long_array" => Value::LongArray(array(value, integer)?),
+        x => return Err(invalid(format!("unknown tag type {}", x))),
+    })
+}
+
+/// Encodes `value` as ordinary JSON for display: numbers lose their NBT type, arrays and lists
+/// both become JSON arrays and compounds become objects. Non-finite floats become `null`.
+pub fn to_plain(value: &Value) -> Json {
+    let float = |v: f64| Number::from_f64(v).map_or(Json::Null, Json::Number);
+    match value {
+        Value::Byte(v) => json!(v),
+        Value::Short(v) => json!(v),
+        Value::Int(v) => json!(v),
+        Value::Long(v) => json!(v),
+        Value::Float(v) => float(*v as f64),
+        Value::Double(v) => float(*v),
+        Value::ByteArray(v) => json!(v),
+        Value::String(v) => json!(v),
+        Value::List(v) => Json::Array(v.iter().map(to_plain).collect()),
+        Value::Compound(v) => Json::Object(v.iter().map(|(k, v)| (k.clone(), to_plain(v))).collect::<Map<_, _>>()),
+        Value::IntArray(v) => json!(v),
+        Value::LongArray(v) => json!(v),
+    }
+}
+
+/// Builds a tree from ordinary JSON, choosing the narrowest of int and long for integers and
+/// double for other numbers. Booleans become bytes; `null` and mixed arrays are rejected.
+pub fn from_plain(json: &Json) -> Result<Value> {
+    Ok(match json {
+        Json::Null => return Err(invalid("null has no NBT equivalent")),
+        Json::Bool(v) => Value::Byte(*v as i8),
+        Json::Number(n) => match n.as_i64() {
+            Some(v) => i32::try_from(v).map_or(Value::Long(v), Value::Int),
+            None if n.is_u64() => return Err(invalid(format!("{} does not fit in a long", n))),
+            None => Value::Double(number(json)?),
+        },
+        Json::String(v) => Value::String(v.clone()),
+        Json::Array(values) => {
+            let list = values.iter().map(from_plain).collect::<Result<Vec<_>>>()?;
+            if list.windows(2).any(|w| w[0].tag() != w[1].tag()) {
+                return Err(Error::ListTypeNotSame);
+            }
+            Value::List(list)
+        }
+        Json::Object(entries) => {
+            let mut compound = Compound::with_capacity(entries.len());
+            for (key, value) in entries {
+                compound.insert(key.clone(), from_plain(value)?);
+            }
+            Value::Compound(compound)
+        }
+    })
+}
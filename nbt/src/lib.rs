@@ -1,48 +1,91 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 use std::io;
 
+// Lets code generated by `nbt-derive` refer to `::nbt` from this crate's own tests.
+extern crate self as nbt;
+
 #[cfg(test)]
 pub mod test;
 
+pub mod bedrock;
+pub mod chunk;
+pub mod codec;
+pub mod compound;
+pub mod compression;
+pub mod de;
+pub mod dialect;
+pub mod diff;
+pub mod json;
+pub mod lz4;
+pub mod mutf8;
+pub mod path;
 pub mod reader;
+pub mod region;
+pub mod ser;
+pub mod snbt;
+pub mod tree;
+pub mod value_ref;
 pub mod writer;
 
-pub struct Nbt;
+pub use bedrock::{BedrockNbt, BedrockNetworkNbt};
+pub use chunk::{BlockState, Chunk};
+pub use codec::{FromNbt, ToNbt};
+pub use compound::{Compound, DuplicateKeys};
+#[cfg(feature = "derive")]
+pub use nbt_derive::{FromNbt, ToNbt};
+pub use compression::{read_compressed, write_gzip, write_lz4, write_zlib, Compression};
+pub use de::from_value;
+pub use dialect::{Dialect, DialectKind, Nbt, NbtAfter764};
+pub use diff::{diff, merge, Patch};
+pub use path::NbtPath;
+pub use ser::to_value;
+pub use tree::TreeOptions;
+pub use value_ref::ValueRef;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub trait NbtReadTrait {
+    fn read_byte_array<R: reader::NbtSource>(r: &mut R) -> Result<Vec<i8>>;
+    fn read_nbt_string<R: reader::NbtSource>(r: &mut R) -> Result<String>;
+    fn read_int_array<R: reader::NbtSource>(r: &mut R) -> Result<Vec<i32>>;
+    fn read_long_array<R: reader::NbtSource>(r: &mut R) -> Result<Vec<i64>>;
+    fn read_list<R: reader::NbtSource>(r: &mut R) -> Result<Vec<Value>>;
+    fn read_compound<R: reader::NbtSource>(r: &mut R) -> Result<Compound>;
+    fn from_reader_named<R: reader::NbtSource>(r: &mut R) -> Result<NamedTag>;
 
-impl Nbt {
-    pub fn new() -> Nbt {
-        Nbt
+    /// Reads the root compound, dropping its name.
+    fn from_reader<R: reader::NbtSource>(r: &mut R) -> Result<Value> {
+        Ok(Self::from_reader_named(r)?.value)
     }
+
+    fn from_reader_ref<'a>(r: &mut reader::NbtReader<'a>) -> Result<value_ref::ValueRef<'a>>;
 }
 
-pub struct NbtAfter764;
+pub trait NbtWriteTrait {
+    fn write_byte_array<W: writer::NbtSink>(w: &mut W, value: &[i8]) -> Result<()>;
+    fn write_nbt_string<W: writer::NbtSink>(w: &mut W, value: &str) -> Result<()>;
+    fn write_int_array<W: writer::NbtSink>(w: &mut W, value: &[i32]) -> Result<()>;
+    fn write_long_array<W: writer::NbtSink>(w: &mut W, value: &[i64]) -> Result<()>;
+    fn write_list<W: writer::NbtSink>(w: &mut W, value: &[Value]) -> Result<()>;
+    fn write_compound<W: writer::NbtSink>(w: &mut W, value: &[(String, Value)]) -> Result<()>;
+    fn write_to<W: writer::NbtSink>(w: &mut W, value: &Value) -> Result<()>;
+    fn write_to_with_name<W: writer::NbtSink>(w: &mut W, name: &str, value: &Value) -> Result<()>;
 
-impl NbtAfter764 {
-    pub fn new() -> NbtAfter764 {
-        NbtAfter764
+    fn write_named<W: writer::NbtSink>(w: &mut W, tag: &NamedTag) -> Result<()> {
+        Self::write_to_with_name(w, &tag.name, &tag.value)
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
-
-pub trait NbtReadTrait {
-    fn read_byte_array(r: &mut reader::NbtReader) -> Result<Vec<i8>>;
-    fn read_nbt_string(r: &mut reader::NbtReader) -> Result<String>;
-    fn read_int_array(r: &mut reader::NbtReader) -> Result<Vec<i32>>;
-    fn read_long_array(r: &mut reader::NbtReader) -> Result<Vec<i64>>;
-    fn read_list(r: &mut reader::NbtReader) -> Result<Vec<Value>>;
-    fn read_compound(r: &mut reader::NbtReader) -> Result<Vec<(String, Value)>>;
-    fn from_reader(r: &mut reader::NbtReader) -> Result<Value>;
+pub fn to_bytes<D: NbtWriteTrait, T: serde::Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut w = writer::NbtWriter::new();
+    D::write_to(&mut w, &to_value(value)?)?;
+    Ok(w.data)
 }
 
-pub trait NbtWriteTrait {
-    fn write_byte_array(w: &mut writer::NbtWriter, value: &[i8]);
-    fn write_nbt_string(w: &mut writer::NbtWriter, value: &str);
-    fn write_int_array(w: &mut writer::NbtWriter, value: &[i32]);
-    fn write_long_array(w: &mut writer::NbtWriter, value: &[i64]);
-    fn write_list(w: &mut writer::NbtWriter, value: &[Value]) -> Result<()>;
-    fn write_compound(w: &mut writer::NbtWriter, name: Option<&String>, value: &[(String, Value)]) -> Result<()>;
-    fn write_to(w: &mut writer::NbtWriter, value: &Value) -> Result<()>;
-    fn write_to_with_name(w: &mut writer::NbtWriter, name: &str, value: &Value) -> Result<()>;
+pub fn from_slice<D: NbtReadTrait, T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T> {
+    let mut r = reader::NbtReader::new(data);
+    from_value(D::from_reader(&mut r)?)
 }
 
 #[derive(Debug)]
@@ -52,9 +95,29 @@ pub enum Error {
     InvalidTag(u8),
     RootTagNotCompound(u8),
     ListTypeNotSame,
+    UnexpectedEof { offset: usize, needed: usize },
+    NegativeLength(i32),
+    ArrayTooLong { len: usize, max: usize },
+    DepthLimitExceeded(usize),
+    SizeLimitExceeded { accounted: u64, max: u64 },
+    InvalidMutf8(usize),
+    StringTooLong { len: usize, max: usize },
+    UnsupportedNone,
+    Custom(String),
+    Snbt { offset: usize, message: String },
+    Region(String),
+    Chunk(String),
+    InvalidPath { offset: usize, message: String },
+    VarIntTooLong(usize),
+    Unsupported(&'static str),
+    TagMismatch { expected: u8, found: u8 },
+    MissingField(String),
+    DuplicateKey(String),
+    PatchConflict { path: String, message: String },
+    Json(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Value {
     Byte(i8),
     Short(i16),
@@ -65,11 +128,49 @@ pub enum Value {
     ByteArray(Vec<i8>),
     String(String),
     List(Vec<Value>),
-    Compound(Option<String>, Vec<(String, Value)>),
+    Compound(Compound),
     IntArray(Vec<i32>),
     LongArray(Vec<i64>),
 }
 
+/// A root tag and the name it is stored under.
+///
+/// Only the root of a document has a name of its own; entries of a compound are named by their
+/// key. Dialects without root names read an empty name and ignore it when writing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NamedTag {
+    pub name: String,
+    pub value: Value,
+}
+
+impl NamedTag {
+    pub fn new(name: impl Into<String>, value: Value) -> NamedTag {
+        NamedTag { name: name.into(), value }
+    }
+}
+
+macro_rules! as_primitive {
+    ($name:ident, $variant:ident, $ty:ty) => {
+        pub fn $name(&self) -> Option<$ty> {
+            match self {
+                Value::$variant(v) => Some(*v),
+                _ => None,
+            }
+        }
+    };
+}
+
+macro_rules! as_slice {
+    ($name:ident, $variant:ident, $ty:ty) => {
+        pub fn $name(&self) -> Option<&[$ty]> {
+            match self {
+                Value::$variant(v) => Some(v),
+                _ => None,
+            }
+        }
+    };
+}
+
 impl Value {
     pub fn tag(&self) -> u8 {
         match self {
@@ -82,46 +183,159 @@ impl Value {
             Value::ByteArray(_) => 7,
             Value::String(_) => 8,
             Value::List(_) => 9,
-            Value::Compound(_, _) => 10,
+            Value::Compound(_) => 10,
             Value::IntArray(_) => 11,
             Value::LongArray(_) => 12,
         }
     }
 
+    /// The entry stored under `key`, if this is a compound that has one.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_compound()?.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.as_compound_mut()?.get_mut(key)
+    }
+
+    /// Whether `pattern` is contained in this tag, like vanilla's `NbtUtils.compareNbt` as used by
+    /// item predicates and `/data` filters. See [`path::matches`].
+    pub fn matches(&self, pattern: &Value) -> bool {
+        path::matches(pattern, self)
+    }
+
+    /// Everything `path` selects, see [`NbtPath`].
+    pub fn query(&self, path: &str) -> Result<Vec<&Value>> {
+        Ok(NbtPath::parse(path)?.get(self))
+    }
+
+    // Each accessor only matches its own tag; an `Int` is not an `i64`.
+    as_primitive!(as_i8, Byte, i8);
+    as_primitive!(as_i16, Short, i16);
+    as_primitive!(as_i32, Int, i32);
+    as_primitive!(as_i64, Long, i64);
+    as_primitive!(as_f32, Float, f32);
+    as_primitive!(as_f64, Double, f64);
+    as_slice!(as_byte_array, ByteArray, i8);
+    as_slice!(as_int_array, IntArray, i32);
+    as_slice!(as_long_array, LongArray, i64);
+    as_slice!(as_list, List, Value);
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Value::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::List(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound_mut(&mut self) -> Option<&mut Compound> {
+        match self {
+            Value::Compound(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The payload for display: numbers and strings as they are, everything else as SNBT.
     pub fn value(&self) -> String {
         match self {
-            Value::Byte(v) => format!("{}", v),
-            Value::Short(v) => format!("{}", v),
-            Value::Int(v) => format!("{}", v),
-            Value::Long(v) => format!("{}", v),
-            Value::Float(v) => format!("{}", v),
-            Value::Double(v) => format!("{}", v),
-            Value::ByteArray(v) => format!("{:?}", v),
-            Value::String(v) => format!("{}", v),
-            Value::List(v) => {
-                let mut i = 0;
-                let mut str = String::new();
-                for value in v {
-                    str = format!("List(index: {}, tag: {}, value{}\n)", i, value.tag(), value.value());
-                    i+=1;
-                };
-                str
-            },
-            Value::Compound(s, v) => {
-                let mut i = 0;
-                let mut str = String::new();
-                let s = match s {
-                    Some(ss) => ss.clone(),
-                    None => "".to_string(),
-                };
-                for (key, value) in v {
-                    str = format!("Name: {} Compound(index: {}, key: {}, tag: {}, value{}\n)", s, i, key, value.tag(), value.value());
-                    i+=1;
-                };
-                str
-            },
-            Value::IntArray(v) => format!("{:?}", v),
-            Value::LongArray(v) => format!("{:?}", v),
+            Value::Byte(v) => v.to_string(),
+            Value::Short(v) => v.to_string(),
+            Value::Int(v) => v.to_string(),
+            Value::Long(v) => v.to_string(),
+            Value::Float(v) => v.to_string(),
+            Value::Double(v) => v.to_string(),
+            Value::String(v) => v.clone(),
+            v => snbt::to_string(v),
+        }
+    }
+
+    /// An indented tree of this tag and everything below it, see [`tree::Tree`].
+    pub fn tree(&self, options: TreeOptions) -> tree::Tree<'_> {
+        tree::Tree::new(self, options)
+    }
+}
+
+/// Floats compare by their bits, so `NaN` equals itself and `0.0` differs from `-0.0`, which
+/// keeps `Eq`, `Hash` and `Ord` consistent. Compounds compare regardless of entry order.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Byte(a), Value::Byte(b)) => a == b,
+            (Value::Short(a), Value::Short(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Long(a), Value::Long(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Double(a), Value::Double(b)) => a.to_bits() == b.to_bits(),
+            (Value::ByteArray(a), Value::ByteArray(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Compound(a), Value::Compound(b)) => a == b,
+            (Value::IntArray(a), Value::IntArray(b)) => a == b,
+            (Value::LongArray(a), Value::LongArray(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(self.tag());
+        match self {
+            Value::Byte(v) => v.hash(state),
+            Value::Short(v) => v.hash(state),
+            Value::Int(v) => v.hash(state),
+            Value::Long(v) => v.hash(state),
+            Value::Float(v) => v.to_bits().hash(state),
+            Value::Double(v) => v.to_bits().hash(state),
+            Value::ByteArray(v) => v.hash(state),
+            Value::String(v) => v.hash(state),
+            Value::List(v) => v.hash(state),
+            Value::Compound(v) => v.hash(state),
+            Value::IntArray(v) => v.hash(state),
+            Value::LongArray(v) => v.hash(state),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Tags of different types are ordered by tag id; floats use `total_cmp`.
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Byte(a), Value::Byte(b)) => a.cmp(b),
+            (Value::Short(a), Value::Short(b)) => a.cmp(b),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Long(a), Value::Long(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::Double(a), Value::Double(b)) => a.total_cmp(b),
+            (Value::ByteArray(a), Value::ByteArray(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::List(a), Value::List(b)) => a.cmp(b),
+            (Value::Compound(a), Value::Compound(b)) => a.cmp(b),
+            (Value::IntArray(a), Value::IntArray(b)) => a.cmp(b),
+            (Value::LongArray(a), Value::LongArray(b)) => a.cmp(b),
+            (a, b) => a.tag().cmp(&b.tag()),
         }
     }
 }
@@ -134,12 +348,39 @@ impl std::fmt::Display for Error {
             Error::Io(e) => write!(f, "IO error: {}", e),
             Error::Utf8(e) => write!(f, "UTF-8 error: {}", e),
             Error::InvalidTag(tag) => write!(f, "Invalid tag: {}", tag),
+            Error::UnexpectedEof { offset, needed } => write!(f, "Unexpected end of data at offset {}, needed {} more bytes", offset, needed),
+            Error::NegativeLength(len) => write!(f, "Negative length: {}", len),
+            Error::ArrayTooLong { len, max } => write!(f, "Array length {} exceeds maximum of {}", len, max),
+            Error::DepthLimitExceeded(max) => write!(f, "Tried to read NBT tag with too high complexity, depth > {}", max),
+            Error::InvalidMutf8(offset) => write!(f, "Invalid modified UTF-8 at byte {}", offset),
+            Error::StringTooLong { len, max } => write!(f, "String of {} bytes exceeds the maximum of {}", len, max),
+            Error::UnsupportedNone => write!(f, "None can only be serialized as a compound field"),
+            Error::Custom(msg) => write!(f, "{}", msg),
+            Error::Snbt { offset, message } => write!(f, "{} at position {}", message, offset),
+            Error::InvalidPath { offset, message } => write!(f, "Invalid NBT path: {} at position {}", message, offset),
+            Error::VarIntTooLong(max) => write!(f, "VarInt is longer than {} bytes", max),
+            Error::Unsupported(what) => write!(f, "Unsupported: {}", what),
+            Error::TagMismatch { expected, found } => write!(f, "Expected tag {}, found {}", expected, found),
+            Error::MissingField(key) => write!(f, "Missing field: {}", key),
+            Error::DuplicateKey(key) => write!(f, "Duplicate key in compound: {}", key),
+            Error::Json(msg) => write!(f, "JSON conversion error: {}", msg),
+            Error::PatchConflict { path, message } => write!(f, "Patch does not apply at '{}': {}", path, message),
+            Error::Region(msg) => write!(f, "Region file error: {}", msg),
+            Error::Chunk(msg) => write!(f, "Chunk error: {}", msg),
+            Error::SizeLimitExceeded { accounted, max } => write!(f, "Tried to read NBT tag that was too big; tried to allocate: {} bytes where max allowed: {}", accounted, max),
         }
     }
 }
 
+impl std::error::Error for Error {}
+
+/// `{}` renders an uncoloured [`tree`](Value::tree) and `{:#}` multi-line SNBT.
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value())
+        if f.alternate() {
+            snbt::write_pretty(f, self, 0)
+        } else {
+            write!(f, "{}", self.tree(TreeOptions::default()))
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,198 @@
+use std::io::{self, Read, Write};
+
+// The framing of lz4-java's `LZ4BlockOutputStream`, which Minecraft uses for LZ4 compressed chunks.
+const MAGIC: &[u8; 8] = b"LZ4Block";
+const METHOD_RAW: u8 = 0x10;
+const METHOD_LZ4: u8 = 0x20;
+const COMPRESSION_LEVEL_BASE: u32 = 10;
+const BLOCK_SIZE: usize = 1 << 16;
+const SEED: u32 = 0x9747b28c;
+
+fn checksum(data: &[u8]) -> u32 {
+    xxh32(data, SEED) & 0x0fff_ffff
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn write_header<W: Write>(w: &mut W, method: u8, compressed: usize, original: usize, checksum: u32) -> io::Result<()> {
+    let level = BLOCK_SIZE.trailing_zeros() - COMPRESSION_LEVEL_BASE;
+    let mut header = [0u8; 21];
+    header[..8].copy_from_slice(MAGIC);
+    header[8] = method | level as u8;
+    header[9..13].copy_from_slice(&(compressed as u32).to_le_bytes());
+    header[13..17].copy_from_slice(&(original as u32).to_le_bytes());
+    header[17..].copy_from_slice(&checksum.to_le_bytes());
+    w.write_all(&header)
+}
+
+/// Writes blocks of up to 64 KiB, each stored raw when LZ4 does not make it smaller.
+pub struct Lz4BlockWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Lz4BlockWriter<W> {
+    pub fn new(inner: W) -> Lz4BlockWriter<W> {
+        Lz4BlockWriter { inner, buf: Vec::with_capacity(BLOCK_SIZE) }
+    }
+
+    /// Writes the pending block and the empty block that ends the stream.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_block()?;
+        write_header(&mut self.inner, METHOD_RAW, 0, 0, 0)?;
+        Ok(self.inner)
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let compressed = lz4_flex::block::compress(&self.buf);
+        let (method, data) = if compressed.len() < self.buf.len() {
+            (METHOD_LZ4, &compressed[..])
+        } else {
+            (METHOD_RAW, &self.buf[..])
+        };
+        write_header(&mut self.inner, method, data.len(), self.buf.len(), checksum(&self.buf))?;
+        self.inner.write_all(data)?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Lz4BlockWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() == BLOCK_SIZE {
+            self.write_block()?;
+        }
+        let n = data.len().min(BLOCK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.flush()
+    }
+}
+
+/// Reads blocks until the empty block that ends the stream, verifying each checksum.
+pub struct Lz4BlockReader<R: Read> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+impl<R: Read> Lz4BlockReader<R> {
+    pub fn new(inner: R) -> Lz4BlockReader<R> {
+        Lz4BlockReader { inner, buf: Vec::new(), pos: 0, finished: false }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn read_block(&mut self) -> io::Result<()> {
+        let mut header = [0u8; 21];
+        self.inner.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(invalid("LZ4 block stream has an invalid magic"));
+        }
+        let method = header[8] & 0xf0;
+        let max_len = 1usize << ((header[8] & 0x0f) as u32 + COMPRESSION_LEVEL_BASE);
+        let compressed = u32::from_le_bytes(header[9..13].try_into().unwrap()) as usize;
+        let original = u32::from_le_bytes(header[13..17].try_into().unwrap()) as usize;
+        let expected = u32::from_le_bytes(header[17..].try_into().unwrap());
+        if original > max_len || compressed > max_len.max(original) || (method == METHOD_RAW && compressed != original) {
+            return Err(invalid("LZ4 block has invalid lengths"));
+        }
+
+        self.buf.clear();
+        self.pos = 0;
+        if original == 0 {
+            if compressed != 0 || expected != 0 {
+                return Err(invalid("LZ4 end block is not empty"));
+            }
+            self.finished = true;
+            return Ok(());
+        }
+        let mut data = vec![0u8; compressed];
+        self.inner.read_exact(&mut data)?;
+        match method {
+            METHOD_RAW => self.buf = data,
+            METHOD_LZ4 => {
+                self.buf.resize(original, 0);
+                let n = lz4_flex::block::decompress_into(&data, &mut self.buf).map_err(|e| invalid(&e.to_string()))?;
+                if n != original {
+                    return Err(invalid("LZ4 block decompressed to the wrong length"));
+                }
+            }
+            _ => return Err(invalid("LZ4 block has an unknown compression method")),
+        }
+        if checksum(&self.buf) != expected {
+            return Err(invalid("LZ4 block checksum mismatch"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Lz4BlockReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.finished || out.is_empty() {
+                return Ok(0);
+            }
+            self.read_block()?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// 32-bit xxHash, used for the block checksums.
+pub fn xxh32(data: &[u8], seed: u32) -> u32 {
+    const P1: u32 = 0x9e3779b1;
+    const P2: u32 = 0x85ebca77;
+    const P3: u32 = 0xc2b2ae3d;
+    const P4: u32 = 0x27d4eb2f;
+    const P5: u32 = 0x165667b1;
+
+    let word = |b: &[u8]| u32::from_le_bytes(b.try_into().unwrap());
+    let round = |acc: u32, lane: u32| acc.wrapping_add(lane.wrapping_mul(P2)).rotate_left(13).wrapping_mul(P1);
+
+    let mut stripes = data.chunks_exact(16);
+    let mut h = if data.len() >= 16 {
+        let mut v = [seed.wrapping_add(P1).wrapping_add(P2), seed.wrapping_add(P2), seed, seed.wrapping_sub(P1)];
+        for stripe in &mut stripes {
+            for (i, lane) in v.iter_mut().enumerate() {
+                *lane = round(*lane, word(&stripe[i * 4..i * 4 + 4]));
+            }
+        }
+        v[0].rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(P5)
+    };
+    h = h.wrapping_add(data.len() as u32);
+
+    let mut words = stripes.remainder().chunks_exact(4);
+    for w in &mut words {
+        h = h.wrapping_add(word(w).wrapping_mul(P3)).rotate_left(17).wrapping_mul(P4);
+    }
+    for &b in words.remainder() {
+        h = h.wrapping_add((b as u32).wrapping_mul(P5)).rotate_left(11).wrapping_mul(P1);
+    }
+
+    h ^= h >> 15;
+    h = h.wrapping_mul(P2);
+    h ^= h >> 13;
+    h = h.wrapping_mul(P3);
+    h ^ (h >> 16)
+}
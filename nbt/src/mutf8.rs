@@ -0,0 +1,85 @@
+//! Java's Modified UTF-8, as written by `DataOutput.writeUTF`.
+//!
+//! It differs from UTF-8 in two ways: NUL is encoded as the two bytes `C0 80`,
+//! and characters outside the Basic Multilingual Plane are encoded as a pair of
+//! three byte surrogates instead of a single four byte sequence.
+
+use std::borrow::Cow;
+
+use crate::{Error, Result};
+
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    // The sequences specific to Modified UTF-8 are all invalid UTF-8, so anything that
+    // passes as UTF-8 decodes to the same string.
+    if let Ok(v) = std::str::from_utf8(bytes) {
+        return Ok(Cow::Borrowed(v));
+    }
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let a = bytes[i] as u16;
+        if a < 0x80 {
+            units.push(a);
+            i += 1;
+        } else if a & 0xe0 == 0xc0 {
+            let b = continuation(bytes, i + 1)?;
+            units.push((a & 0x1f) << 6 | b);
+            i += 2;
+        } else if a & 0xf0 == 0xe0 {
+            let b = continuation(bytes, i + 1)?;
+            let c = continuation(bytes, i + 2)?;
+            units.push((a & 0x0f) << 12 | b << 6 | c);
+            i += 3;
+        } else {
+            return Err(Error::InvalidMutf8(i));
+        }
+    }
+    match String::from_utf16(&units) {
+        Ok(v) => Ok(Cow::Owned(v)),
+        Err(_) => Err(Error::InvalidMutf8(bytes.len())),
+    }
+}
+
+#[inline]
+fn continuation(bytes: &[u8], i: usize) -> Result<u16> {
+    match bytes.get(i) {
+        Some(&b) if b & 0xc0 == 0x80 => Ok((b & 0x3f) as u16),
+        _ => Err(Error::InvalidMutf8(i)),
+    }
+}
+
+pub fn encoded_len(value: &str) -> usize {
+    value.chars().map(|c| match c as u32 {
+        0 => 2,
+        0x01..=0x7f => 1,
+        0x80..=0x7ff => 2,
+        0x800..=0xffff => 3,
+        _ => 6,
+    }).sum()
+}
+
+pub fn encode(value: &str) -> Cow<'_, [u8]> {
+    if !value.bytes().any(|b| b == 0 || b >= 0xf0) {
+        return Cow::Borrowed(value.as_bytes());
+    }
+    let mut bytes = Vec::with_capacity(encoded_len(value));
+    for c in value.chars() {
+        match c as u32 {
+            0 => bytes.extend_from_slice(&[0xc0, 0x80]),
+            0x01..=0xffff => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            _ => {
+                let mut buf = [0; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    let unit = *unit;
+                    bytes.push(0xe0 | (unit >> 12) as u8);
+                    bytes.push(0x80 | (unit >> 6 & 0x3f) as u8);
+                    bytes.push(0x80 | (unit & 0x3f) as u8);
+                }
+            }
+        }
+    }
+    Cow::Owned(bytes)
+}
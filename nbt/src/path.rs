@@ -0,0 +1,525 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{snbt, Compound, Error, Result, Value};
+
+/// A path in the syntax of the vanilla `/data` command, such as `Level.Sections[3].BlockStates`,
+/// `Inventory[{id:"minecraft:stone"}].Count` or `{Invulnerable:1b}`.
+///
+/// Elements of byte, int and long arrays can be set and removed, but not borrowed, so
+/// lookups only step into lists.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NbtPath {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    /// `name`
+    Child(String),
+    /// `name{pattern}`
+    MatchChild(String, Value),
+    /// `{pattern}`, only allowed first
+    MatchRoot(Value),
+    /// `[]`
+    AllElements,
+    /// `[index]`, negative indices count from the end
+    Index(i32),
+    /// `[{pattern}]`
+    MatchElement(Value),
+}
+
+impl Node {
+    // The container created when this node is applied to a missing parent.
+    fn empty_parent(&self) -> Value {
+        match self {
+            Node::Child(_) | Node::MatchChild(..) | Node::MatchRoot(_) => Value::Compound(Compound::new()),
+            Node::AllElements | Node::Index(_) | Node::MatchElement(_) => Value::List(Vec::new()),
+        }
+    }
+}
+
+/// Whether every entry of `pattern` is present in `value`, the way vanilla matches path patterns.
+///
+/// Compounds match when each of the pattern's keys matches, and lists when each of the pattern's
+/// elements matches some element; an empty list pattern only matches an empty list.
+pub fn matches(pattern: &Value, value: &Value) -> bool {
+    match (pattern, value) {
+        (Value::Compound(pattern), Value::Compound(entries)) => pattern
+            .iter()
+            .all(|(key, p)| entries.get(key).is_some_and(|v| matches(p, v))),
+        (Value::List(pattern), Value::List(values)) => {
+            if pattern.is_empty() {
+                values.is_empty()
+            } else {
+                pattern.iter().all(|p| values.iter().any(|v| matches(p, v)))
+            }
+        }
+        _ => pattern == value,
+    }
+}
+
+fn resolve_index(index: i32, len: usize) -> Option<usize> {
+    let i = if index < 0 { len as i64 + index as i64 } else { index as i64 };
+    (0..len as i64).contains(&i).then_some(i as usize)
+}
+
+fn list_accepts(list: &[Value], value: &Value) -> bool {
+    list.first().is_none_or(|first| first.tag() == value.tag())
+}
+
+fn collect<'a>(nodes: &[Node], value: &'a Value, out: &mut Vec<&'a Value>) {
+    let Some((node, rest)) = nodes.split_first() else {
+        out.push(value);
+        return;
+    };
+    match node {
+        Node::Child(name) => {
+            if let Some(v) = value.get(name) {
+                collect(rest, v, out);
+            }
+        }
+        Node::MatchChild(name, pattern) => {
+            if let Some(v) = value.get(name).filter(|v| matches(pattern, v)) {
+                collect(rest, v, out);
+            }
+        }
+        Node::MatchRoot(pattern) => {
+            if matches(pattern, value) {
+                collect(rest, value, out);
+            }
+        }
+        Node::AllElements => {
+            for v in value.as_list().unwrap_or_default() {
+                collect(rest, v, out);
+            }
+        }
+        Node::Index(index) => {
+            if let Some(list) = value.as_list() {
+                if let Some(i) = resolve_index(*index, list.len()) {
+                    collect(rest, &list[i], out);
+                }
+            }
+        }
+        Node::MatchElement(pattern) => {
+            for v in value.as_list().unwrap_or_default().iter().filter(|v| matches(pattern, v)) {
+                collect(rest, v, out);
+            }
+        }
+    }
+}
+
+// Like `collect`, but stops when `stop` nodes are left. When `create` is set, missing children
+// and list elements are added on the way, shaped for the node that follows them.
+fn collect_mut<'a>(nodes: &[Node], value: &'a mut Value, stop: usize, create: bool, out: &mut Vec<&'a mut Value>) {
+    if nodes.len() <= stop {
+        out.push(value);
+        return;
+    }
+    let (node, rest) = nodes.split_first().unwrap();
+    match node {
+        Node::Child(name) => {
+            if create && value.as_compound().is_some() && value.get(name).is_none() {
+                let entries = value.as_compound_mut().unwrap();
+                entries.insert(name.clone(), rest[0].empty_parent());
+            }
+            if let Some(v) = value.get_mut(name) {
+                collect_mut(rest, v, stop, create, out);
+            }
+        }
+        Node::MatchChild(name, pattern) => {
+            if create && value.as_compound().is_some() && value.get(name).is_none() {
+                let entries = value.as_compound_mut().unwrap();
+                entries.insert(name.clone(), pattern.clone());
+            }
+            if let Some(v) = value.get_mut(name).filter(|v| matches(pattern, v)) {
+                collect_mut(rest, v, stop, create, out);
+            }
+        }
+        Node::MatchRoot(pattern) => {
+            if matches(pattern, value) {
+                collect_mut(rest, value, stop, create, out);
+            }
+        }
+        Node::AllElements => {
+            if let Some(list) = value.as_list_mut() {
+                if create && list.is_empty() {
+                    list.push(rest[0].empty_parent());
+                }
+                for v in list {
+                    collect_mut(rest, v, stop, create, out);
+                }
+            }
+        }
+        Node::Index(index) => {
+            if let Some(list) = value.as_list_mut() {
+                if let Some(i) = resolve_index(*index, list.len()) {
+                    collect_mut(rest, &mut list[i], stop, create, out);
+                }
+            }
+        }
+        Node::MatchElement(pattern) => {
+            if let Some(list) = value.as_list_mut() {
+                if create && !list.iter().any(|v| matches(pattern, v)) && list_accepts(list, pattern) {
+                    list.push(pattern.clone());
+                }
+                for v in list.iter_mut().filter(|v| matches(pattern, v)) {
+                    collect_mut(rest, v, stop, create, out);
+                }
+            }
+        }
+    }
+}
+
+macro_rules! set_array_element {
+    ($array:expr, $variant:ident, $range:expr, $value:expr) => {
+        match $value {
+            Value::$variant(v) => {
+                let range = $range;
+                $array[range.clone()].fill(*v);
+                range.len()
+            }
+            _ => 0,
+        }
+    };
+}
+
+// Sets the elements selected by `node` in an array, returning how many were written.
+fn set_in_array(parent: &mut Value, node: &Node, value: &Value) -> usize {
+    let len = match parent {
+        Value::ByteArray(v) => v.len(),
+        Value::IntArray(v) => v.len(),
+        Value::LongArray(v) => v.len(),
+        _ => return 0,
+    };
+    let range = match node {
+        Node::Index(index) => match resolve_index(*index, len) {
+            Some(i) => i..i + 1,
+            None => return 0,
+        },
+        Node::AllElements => 0..len,
+        _ => return 0,
+    };
+    match parent {
+        Value::ByteArray(a) => set_array_element!(a, Byte, range, value),
+        Value::IntArray(a) => set_array_element!(a, Int, range, value),
+        Value::LongArray(a) => set_array_element!(a, Long, range, value),
+        _ => 0,
+    }
+}
+
+fn set_node(parent: &mut Value, node: &Node, value: &Value) -> usize {
+    match node {
+        Node::Child(name) => match parent.as_compound_mut() {
+            Some(entries) => {
+                entries.insert(name.clone(), value.clone());
+                1
+            }
+            None => 0,
+        },
+        Node::MatchChild(name, pattern) => match parent.get_mut(name) {
+            Some(v) if matches(pattern, v) => {
+                *v = value.clone();
+                1
+            }
+            _ => 0,
+        },
+        Node::MatchRoot(_) => 0,
+        Node::AllElements => match parent.as_list_mut() {
+            Some(list) if list.is_empty() => {
+                list.push(value.clone());
+                1
+            }
+            Some(list) if list.len() == 1 || list_accepts(list, value) => {
+                list.iter_mut().for_each(|v| *v = value.clone());
+                list.len()
+            }
+            Some(_) => 0,
+            None => set_in_array(parent, node, value),
+        },
+        Node::Index(index) => match parent.as_list_mut() {
+            Some(list) => match resolve_index(*index, list.len()) {
+                Some(i) if list.len() == 1 || list_accepts(list, value) => {
+                    list[i] = value.clone();
+                    1
+                }
+                _ => 0,
+            },
+            None => set_in_array(parent, node, value),
+        },
+        Node::MatchElement(pattern) => match parent.as_list_mut() {
+            Some(list) if list_accepts(list, value) => {
+                let mut n = 0;
+                for v in list.iter_mut().filter(|v| matches(pattern, v)) {
+                    *v = value.clone();
+                    n += 1;
+                }
+                n
+            }
+            _ => 0,
+        },
+    }
+}
+
+fn remove_node(parent: &mut Value, node: &Node) -> usize {
+    fn retain<T>(values: &mut Vec<T>, mut keep: impl FnMut(usize, &T) -> bool) -> usize {
+        let len = values.len();
+        let mut i = 0;
+        values.retain(|v| {
+            i += 1;
+            keep(i - 1, v)
+        });
+        len - values.len()
+    }
+
+    match node {
+        Node::Child(name) => parent.as_compound_mut().and_then(|entries| entries.remove(name)).map_or(0, |_| 1),
+        Node::MatchChild(name, pattern) => match parent.get(name) {
+            Some(v) if matches(pattern, v) => parent.as_compound_mut().and_then(|entries| entries.remove(name)).map_or(0, |_| 1),
+            _ => 0,
+        },
+        Node::MatchRoot(_) => 0,
+        Node::AllElements | Node::Index(_) => {
+            // `[]` keeps nothing, `[i]` everything but element i
+            let keep = |len: usize| {
+                move |i: usize| match node {
+                    Node::Index(index) => resolve_index(*index, len) != Some(i),
+                    _ => false,
+                }
+            };
+            match parent {
+                Value::List(v) => {
+                    let keep = keep(v.len());
+                    retain(v, |i, _| keep(i))
+                }
+                Value::ByteArray(v) => {
+                    let keep = keep(v.len());
+                    retain(v, |i, _| keep(i))
+                }
+                Value::IntArray(v) => {
+                    let keep = keep(v.len());
+                    retain(v, |i, _| keep(i))
+                }
+                Value::LongArray(v) => {
+                    let keep = keep(v.len());
+                    retain(v, |i, _| keep(i))
+                }
+                _ => 0,
+            }
+        }
+        Node::MatchElement(pattern) => parent.as_list_mut().map_or(0, |list| retain(list, |_, v| !matches(pattern, v))),
+    }
+}
+
+impl NbtPath {
+    pub fn parse(s: &str) -> Result<NbtPath> {
+        Parser { s, cursor: 0 }.read_path()
+    }
+
+    /// The empty path, which selects the tag it is applied to. It displays as an empty string,
+    /// which [`NbtPath::parse`] does not accept.
+    pub fn root() -> NbtPath {
+        NbtPath { nodes: Vec::new() }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn push_key(&mut self, key: &str) {
+        self.nodes.push(Node::Child(key.to_string()));
+    }
+
+    pub fn push_index(&mut self, index: i32) {
+        self.nodes.push(Node::Index(index));
+    }
+
+    /// Everything the path selects in `root`.
+    pub fn get<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut out = Vec::new();
+        collect(&self.nodes, root, &mut out);
+        out
+    }
+
+    pub fn get_mut<'a>(&self, root: &'a mut Value) -> Vec<&'a mut Value> {
+        let mut out = Vec::new();
+        collect_mut(&self.nodes, root, 0, false, &mut out);
+        out
+    }
+
+    /// Sets every selected tag to `value`, creating missing compounds and lists along the way
+    /// like `/data modify ... set` does. Returns the number of tags written.
+    pub fn set(&self, root: &mut Value, value: Value) -> usize {
+        let Some(last) = self.nodes.last() else {
+            *root = value;
+            return 1;
+        };
+        let mut targets = Vec::new();
+        collect_mut(&self.nodes, root, 1, true, &mut targets);
+        targets.into_iter().map(|parent| set_node(parent, last, &value)).sum()
+    }
+
+    /// Removes every selected tag, returning how many were removed. The root cannot be removed.
+    pub fn remove(&self, root: &mut Value) -> usize {
+        let Some(last) = self.nodes.last() else {
+            return 0;
+        };
+        let mut targets = Vec::new();
+        collect_mut(&self.nodes, root, 1, false, &mut targets);
+        targets.into_iter().map(|parent| remove_node(parent, last)).sum()
+    }
+}
+
+impl FromStr for NbtPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<NbtPath> {
+        NbtPath::parse(s)
+    }
+}
+
+fn is_unquoted_name_char(c: char) -> bool {
+    !matches!(c, ' ' | '"' | '\'' | '[' | ']' | '.' | '{' | '}')
+}
+
+fn write_name(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    if !name.is_empty() && name.chars().all(is_unquoted_name_char) {
+        f.write_str(name)
+    } else {
+        f.write_str(&snbt::to_string(&Value::String(name.to_string())))
+    }
+}
+
+impl fmt::Display for NbtPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 && matches!(node, Node::Child(_) | Node::MatchChild(..)) {
+                f.write_str(".")?;
+            }
+            match node {
+                Node::Child(name) => write_name(f, name)?,
+                Node::MatchChild(name, pattern) => {
+                    write_name(f, name)?;
+                    f.write_str(&snbt::to_string(pattern))?;
+                }
+                Node::MatchRoot(pattern) => f.write_str(&snbt::to_string(pattern))?,
+                Node::AllElements => f.write_str("[]")?,
+                Node::Index(index) => write!(f, "[{}]", index)?,
+                Node::MatchElement(pattern) => write!(f, "[{}]", snbt::to_string(pattern))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    cursor: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::InvalidPath { offset: self.cursor, message: message.to_string() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.cursor..].chars().next()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("Expected '{}'", expected)));
+        }
+        self.cursor += 1;
+        Ok(())
+    }
+
+    fn read_path(&mut self) -> Result<NbtPath> {
+        let mut nodes = Vec::new();
+        while self.peek().is_some() {
+            nodes.push(self.read_node(nodes.is_empty())?);
+            if !matches!(self.peek(), None | Some('[') | Some('{')) {
+                self.expect('.')?;
+                if self.peek().is_none() {
+                    return Err(self.error("Invalid path element"));
+                }
+            }
+        }
+        if nodes.is_empty() {
+            return Err(self.error("Expected path"));
+        }
+        Ok(NbtPath { nodes })
+    }
+
+    fn read_node(&mut self, root: bool) -> Result<Node> {
+        match self.peek() {
+            Some('{') => {
+                if !root {
+                    return Err(self.error("Invalid path element"));
+                }
+                Ok(Node::MatchRoot(self.read_pattern()?))
+            }
+            Some('[') => {
+                self.cursor += 1;
+                let node = match self.peek() {
+                    Some('{') => Node::MatchElement(self.read_pattern()?),
+                    Some(']') => Node::AllElements,
+                    _ => Node::Index(self.read_int()?),
+                };
+                self.expect(']')?;
+                Ok(node)
+            }
+            Some(quote @ ('"' | '\'')) => {
+                let name = self.read_snbt(|s| snbt::read_quoted_prefix(s, quote))?;
+                self.read_child(name)
+            }
+            _ => {
+                let start = self.cursor;
+                while self.peek().is_some_and(is_unquoted_name_char) {
+                    self.cursor += self.peek().unwrap().len_utf8();
+                }
+                if self.cursor == start {
+                    return Err(self.error("Invalid path element"));
+                }
+                self.read_child(self.s[start..self.cursor].to_string())
+            }
+        }
+    }
+
+    fn read_child(&mut self, name: String) -> Result<Node> {
+        if self.peek() == Some('{') {
+            Ok(Node::MatchChild(name, self.read_pattern()?))
+        } else {
+            Ok(Node::Child(name))
+        }
+    }
+
+    fn read_pattern(&mut self) -> Result<Value> {
+        self.read_snbt(snbt::read_compound_prefix)
+    }
+
+    // Runs one of the SNBT parser's prefix readers from the cursor, so quoting rules stay shared.
+    fn read_snbt<T>(&mut self, read: impl FnOnce(&str) -> Result<(T, usize)>) -> Result<T> {
+        let start = self.cursor;
+        let (value, len) = read(&self.s[start..]).map_err(|e| match e {
+            Error::Snbt { offset, message } => Error::InvalidPath { offset: start + offset, message },
+            e => e,
+        })?;
+        self.cursor += len;
+        Ok(value)
+    }
+
+    fn read_int(&mut self) -> Result<i32> {
+        let start = self.cursor;
+        if self.peek() == Some('-') {
+            self.cursor += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.cursor += 1;
+        }
+        self.s[start..self.cursor].parse().map_err(|_| {
+            self.cursor = start;
+            self.error("Expected an index")
+        })
+    }
+}
@@ -1,269 +1,393 @@
-use crate::{Error, Nbt, NbtAfter764, NbtReadTrait, Result, Value};
+use std::borrow::Cow;
+use std::io::{self, Read};
 
-pub struct NbtReader<'a> {
-    pub data: &'a mut [u8],
-    pub cursor: usize,
+use crate::{mutf8, DuplicateKeys, Error, Result};
+
+// Estimated in-memory sizes of each tag type, as accounted by vanilla's `NbtAccounter`.
+pub(crate) const TAG_SIZES: [u64; 13] = [0, 9, 10, 12, 16, 12, 16, 24, 36, 37, 48, 24, 24];
+pub(crate) const COMPOUND_ENTRY_SIZE: u64 = 36;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NbtLimits {
+    pub max_depth: usize,
+    pub max_bytes: u64,
+    pub max_array_len: usize,
+    pub duplicate_keys: DuplicateKeys,
 }
 
-impl NbtReader<'_> {
-    pub fn new(data: &mut [u8]) -> NbtReader {
-        NbtReader {
-            data,
-            cursor: 0,
+impl NbtLimits {
+    pub const MAX_DEPTH: usize = 512;
+    pub const NETWORK_MAX_BYTES: u64 = 2 * 1024 * 1024;
+
+    /// Quota used by vanilla for NBT received over the network.
+    pub fn network() -> NbtLimits {
+        NbtLimits {
+            max_bytes: Self::NETWORK_MAX_BYTES,
+            ..NbtLimits::default()
+        }
+    }
+
+    pub fn unlimited() -> NbtLimits {
+        NbtLimits {
+            max_depth: usize::MAX,
+            max_bytes: u64::MAX,
+            max_array_len: usize::MAX,
+            duplicate_keys: DuplicateKeys::default(),
+        }
+    }
+}
+
+/// Unlimited heap with vanilla's depth limit, as used for NBT read from disk.
+impl Default for NbtLimits {
+    fn default() -> NbtLimits {
+        NbtLimits {
+            max_depth: Self::MAX_DEPTH,
+            max_bytes: u64::MAX,
+            max_array_len: usize::MAX,
+            duplicate_keys: DuplicateKeys::default(),
+        }
+    }
+}
+
+pub struct NbtAccounter {
+    pub limits: NbtLimits,
+    depth: usize,
+    accounted: u64,
+}
+
+impl NbtAccounter {
+    pub fn new(limits: NbtLimits) -> NbtAccounter {
+        NbtAccounter {
+            limits,
+            depth: 0,
+            accounted: 0,
         }
     }
 
     #[inline]
-    pub fn roll_back(&mut self, n: usize) {
-        self.cursor -= n;
+    pub fn accounted(&self) -> u64 {
+        self.accounted
     }
 
     #[inline]
-    pub fn roll_down(&mut self, n: usize) {
-        self.cursor += n;
+    pub fn account(&mut self, bytes: u64) -> Result<()> {
+        self.accounted = self.accounted.saturating_add(bytes);
+        if self.accounted > self.limits.max_bytes {
+            return Err(Error::SizeLimitExceeded { accounted: self.accounted, max: self.limits.max_bytes });
+        }
+        Ok(())
     }
 
     #[inline]
-    pub fn read_u8 (&mut self) -> u8 {
-        let value = self.data[self.cursor];
-        self.cursor += 1;
-        value
+    pub fn push_depth(&mut self) -> Result<()> {
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::DepthLimitExceeded(self.limits.max_depth));
+        }
+        self.depth += 1;
+        Ok(())
     }
 
     #[inline]
-    pub fn read_i8 (&mut self) -> i8 {
-        self.read_u8() as i8
+    pub fn pop_depth(&mut self) {
+        self.depth -= 1;
     }
 
     #[inline]
-    pub fn read_i16(&mut self) -> i16 {
-        let value = i16::from_be_bytes([self.data[self.cursor], self.data[self.cursor + 1]]);
-        self.cursor += 2;
-        value
+    pub fn check_len(&self, len: i32) -> Result<usize> {
+        if len < 0 {
+            return Err(Error::NegativeLength(len));
+        }
+        let len = len as usize;
+        if len > self.limits.max_array_len {
+            return Err(Error::ArrayTooLong { len, max: self.limits.max_array_len });
+        }
+        Ok(len)
     }
+}
+
+/// Decodes big-endian ints a whole slice at a time. Fixed-size chunks leave no per-element bounds
+/// checks in the loop, which leaves the compiler free to vectorise the byte swaps.
+///
+/// Trailing bytes that do not make up a whole int are ignored.
+pub fn i32s_from_be_bytes(bytes: &[u8]) -> Vec<i32> {
+    bytes.as_chunks::<4>().0.iter().map(|&b| i32::from_be_bytes(b)).collect()
+}
+
+pub fn i64s_from_be_bytes(bytes: &[u8]) -> Vec<i64> {
+    bytes.as_chunks::<8>().0.iter().map(|&b| i64::from_be_bytes(b)).collect()
+}
+
+/// A source of NBT bytes, shared by the in-memory and streaming readers.
+pub trait NbtSource {
+    fn accounter(&mut self) -> &mut NbtAccounter;
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]>;
+    fn read_bytes(&mut self, len: usize) -> Result<Cow<'_, [u8]>>;
+    /// How many of `len` elements of `size` bytes are worth allocating up front.
+    fn capacity_hint(&self, len: usize, size: usize) -> usize;
 
     #[inline]
-    pub fn read_i32(&mut self) -> i32 {
-        let value = i32::from_be_bytes([
-            self.data[self.cursor],
-            self.data[self.cursor + 1],
-            self.data[self.cursor + 2],
-            self.data[self.cursor + 3],
-        ]);
-        self.cursor += 4;
-        value
+    fn account(&mut self, bytes: u64) -> Result<()> {
+        self.accounter().account(bytes)
     }
 
     #[inline]
-    pub fn read_i64(&mut self) -> i64 {
-        let value = i64::from_be_bytes([
-            self.data[self.cursor],
-            self.data[self.cursor + 1],
-            self.data[self.cursor + 2],
-            self.data[self.cursor + 3],
-            self.data[self.cursor + 4],
-            self.data[self.cursor + 5],
-            self.data[self.cursor + 6],
-            self.data[self.cursor + 7],
-        ]);
-        self.cursor += 8;
-        value
+    fn account_tag(&mut self, type_id: u8) -> Result<()> {
+        match TAG_SIZES.get(type_id as usize) {
+            Some(&size) => self.account(size),
+            None => Err(Error::InvalidTag(type_id)),
+        }
     }
 
     #[inline]
-    pub fn read_f32(&mut self) -> f32 {
-        let value = f32::from_be_bytes([
-            self.data[self.cursor],
-            self.data[self.cursor + 1],
-            self.data[self.cursor + 2],
-            self.data[self.cursor + 3],
-        ]);
-        self.cursor += 4;
-        value
+    fn push_depth(&mut self) -> Result<()> {
+        self.accounter().push_depth()
     }
 
     #[inline]
-    pub fn read_f64(&mut self) -> f64 {
-        let value = f64::from_be_bytes([
-            self.data[self.cursor],
-            self.data[self.cursor + 1],
-            self.data[self.cursor + 2],
-            self.data[self.cursor + 3],
-            self.data[self.cursor + 4],
-            self.data[self.cursor + 5],
-            self.data[self.cursor + 6],
-            self.data[self.cursor + 7],
-        ]);
-        self.cursor += 8;
-        value
+    fn pop_depth(&mut self) {
+        self.accounter().pop_depth()
     }
 
     #[inline]
-    pub fn read_i8_array(&mut self, len: usize) -> Vec<i8> {
-        let mut value = Vec::with_capacity(len);
-        for _ in 0..len {
-            value.push(self.read_i8());
-        }
-        value
+    fn read_len(&mut self) -> Result<usize> {
+        let len = self.read_i32()?;
+        self.accounter().check_len(len)
     }
 
     #[inline]
-    pub fn read_i32_array(&mut self, len: usize) -> Vec<i32> {
-        let mut value = Vec::with_capacity(len);
-        for _ in 0..len {
-            value.push(self.read_i32());
-        }
-        value
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_array::<1>()?[0])
     }
 
     #[inline]
-    pub fn read_i64_array(&mut self, len: usize) -> Vec<i64> {
-        let mut value = Vec::with_capacity(len);
-        for _ in 0..len {
-            value.push(self.read_i64());
-        }
-        value
+    fn read_i8(&mut self) -> Result<i8> {
+        Ok(self.read_u8()? as i8)
     }
 
     #[inline]
-    pub fn read_string(&mut self, len: usize) -> Result<String> {
-        let value = match String::from_utf8(self.data[self.cursor..self.cursor + len].to_vec()) {
-            Ok(v) => v,
-            Err(e) => return Err(Error::Utf8(e)),
-        };
-        self.cursor += len;
-        Ok(value)
+    fn read_i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.read_array()?))
     }
-}
 
-impl NbtReadTrait for Nbt {
     #[inline]
-    fn read_byte_array(r: &mut self::NbtReader) -> Result<Vec<i8>> {
-        let len = r.read_i32() as usize;
-        Ok(r.read_i8_array(len))
+    fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.read_array()?))
     }
 
     #[inline]
-    fn read_nbt_string(r: &mut self::NbtReader) -> Result<String> {
-        let len = r.read_i16() as usize;
-        r.read_string(len)
+    fn read_i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.read_array()?))
     }
 
     #[inline]
-    fn read_int_array(r: &mut self::NbtReader) -> Result<Vec<i32>> {
-        let len = r.read_i32() as usize;
-        Ok(r.read_i32_array(len))
+    fn read_i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.read_array()?))
     }
 
     #[inline]
-    fn read_long_array(r: &mut self::NbtReader) -> Result<Vec<i64>> {
-        let len = r.read_i32() as usize;
-        Ok(r.read_i64_array(len))
+    fn read_f32(&mut self) -> Result<f32> {
+        Ok(f32::from_be_bytes(self.read_array()?))
     }
 
     #[inline]
-    fn read_list(r: &mut self::NbtReader) -> Result<Vec<Value>> {
-        let type_id = r.read_u8();
-        let len = r.read_i32() as usize;
-        let mut list = Vec::with_capacity(len);
-        for _ in 0..len {
-            let value = match type_id {
-                1 => Value::Byte(r.read_i8()),
-                2 => Value::Short(r.read_i16()),
-                3 => Value::Int(r.read_i32()),
-                4 => Value::Long(r.read_i64()),
-                5 => Value::Float(r.read_f32()),
-                6 => Value::Double(r.read_f64()),
-                7 => Value::ByteArray(Nbt::read_byte_array(r)?),
-                8 => Value::String(Nbt::read_nbt_string(r)?),
-                9 => Value::List(Nbt::read_list(r)?),
-                10 => Value::Compound(None, Nbt::read_compound(r)?),
-                11 => Value::IntArray(Nbt::read_int_array(r)?),
-                12 => Value::LongArray(Nbt::read_long_array(r)?),
-                _ => return Err(Error::InvalidTag(type_id)),
-            };
-            list.push(value);
-        }
-        Ok(list)
+    fn read_f64(&mut self) -> Result<f64> {
+        Ok(f64::from_be_bytes(self.read_array()?))
     }
 
     #[inline]
-    fn read_compound(r: &mut self::NbtReader) -> Result<Vec<(String, Value)>> {
-        let mut compound = Vec::new();
-        loop {
-            let type_id = r.read_u8();
-            if type_id == 0 {
-                break;
-            }
-            let name = Nbt::read_nbt_string(r)?;
-            let value = match type_id {
-                1 => Value::Byte(r.read_i8()),
-                2 => Value::Short(r.read_i16()),
-                3 => Value::Int(r.read_i32()),
-                4 => Value::Long(r.read_i64()),
-                5 => Value::Float(r.read_f32()),
-                6 => Value::Double(r.read_f64()),
-                7 => Value::ByteArray(Nbt::read_byte_array(r)?),
-                8 => Value::String(Nbt::read_nbt_string(r)?),
-                9 => Value::List(Nbt::read_list(r)?),
-                10 => Value::Compound(Some(name.clone()), Nbt::read_compound(r)?),
-                11 => Value::IntArray(Nbt::read_int_array(r)?),
-                12 => Value::LongArray(Nbt::read_long_array(r)?),
-                _ => return Err(Error::InvalidTag(type_id)),
-            };
-            compound.push((name, value));
+    fn read_i8_array(&mut self, len: usize) -> Result<Vec<i8>> {
+        Ok(self.read_bytes(len)?.iter().map(|&b| b as i8).collect())
+    }
+
+    /// Reads `len` big-endian ints in one go; see [`i32s_from_be_bytes`].
+    #[inline]
+    fn read_i32_array(&mut self, len: usize) -> Result<Vec<i32>> {
+        Ok(i32s_from_be_bytes(&self.read_bytes(len.saturating_mul(4))?))
+    }
+
+    #[inline]
+    fn read_i64_array(&mut self, len: usize) -> Result<Vec<i64>> {
+        Ok(i64s_from_be_bytes(&self.read_bytes(len.saturating_mul(8))?))
+    }
+
+    #[inline]
+    fn read_string(&mut self, len: usize) -> Result<String> {
+        Ok(mutf8::decode(&self.read_bytes(len)?)?.into_owned())
+    }
+}
+
+pub struct NbtReader<'a> {
+    pub data: &'a [u8],
+    pub cursor: usize,
+    pub accounter: NbtAccounter,
+}
+
+impl<'a> NbtReader<'a> {
+    pub fn new(data: &'a [u8]) -> NbtReader<'a> {
+        NbtReader::with_limits(data, NbtLimits::default())
+    }
+
+    pub fn with_limits(data: &'a [u8], limits: NbtLimits) -> NbtReader<'a> {
+        NbtReader {
+            data,
+            cursor: 0,
+            accounter: NbtAccounter::new(limits),
         }
-        Ok(compound)
     }
 
-    fn from_reader(r: &mut self::NbtReader) -> Result<Value> {
-        match r.read_u8() {
-            10 => {
-                let name = Nbt::read_nbt_string(r)?;
-                Ok(Value::Compound(Some(name), Nbt::read_compound(r)?))
-            }
-            x => Err(Error::RootTagNotCompound(x)),
+    #[inline]
+    pub fn accounted(&self) -> u64 {
+        self.accounter.accounted()
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.cursor)
+    }
+
+    /// Moves the cursor `n` bytes back; moving before the start is an error.
+    #[inline]
+    pub fn roll_back(&mut self, n: usize) -> Result<()> {
+        self.cursor = self.cursor.checked_sub(n).ok_or(Error::UnexpectedEof { offset: self.cursor, needed: n })?;
+        Ok(())
+    }
+
+    /// Skips `n` bytes; skipping past the end is an error.
+    #[inline]
+    pub fn roll_down(&mut self, n: usize) -> Result<()> {
+        self.ensure(n)?;
+        self.cursor += n;
+        Ok(())
+    }
+
+    #[inline]
+    fn ensure(&self, n: usize) -> Result<()> {
+        if self.remaining() < n {
+            return Err(Error::UnexpectedEof { offset: self.cursor, needed: n });
         }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        self.ensure(n)?;
+        let start = self.cursor;
+        self.cursor += n;
+        Ok(&self.data[start..self.cursor])
     }
 }
 
-impl NbtReadTrait for NbtAfter764 {
+impl NbtSource for NbtReader<'_> {
+    #[inline]
+    fn accounter(&mut self) -> &mut NbtAccounter {
+        &mut self.accounter
+    }
+
     #[inline]
-    fn read_byte_array(r: &mut self::NbtReader) -> Result<Vec<i8>> {
-        Nbt::read_byte_array(r)
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
     }
 
     #[inline]
-    fn read_nbt_string(r: &mut self::NbtReader) -> Result<String> {
-        Nbt::read_nbt_string(r)
+    fn read_bytes(&mut self, len: usize) -> Result<Cow<'_, [u8]>> {
+        Ok(Cow::Borrowed(self.take(len)?))
     }
 
     #[inline]
-    fn read_int_array(r: &mut self::NbtReader) -> Result<Vec<i32>> {
-        Nbt::read_int_array(r)
+    fn capacity_hint(&self, len: usize, size: usize) -> usize {
+        len.min(self.remaining() / size.max(1))
+    }
+}
+
+/// Reads NBT incrementally from any `io::Read`, such as a `GzDecoder` or a socket.
+///
+/// Every primitive is a separate `read_exact` call, so unbuffered sources should be
+/// wrapped with [`NbtStreamReader::buffered`].
+pub struct NbtStreamReader<R> {
+    inner: R,
+    position: usize,
+    pub accounter: NbtAccounter,
+}
+
+impl<R: io::Read> NbtStreamReader<R> {
+    pub fn new(inner: R) -> NbtStreamReader<R> {
+        NbtStreamReader::with_limits(inner, NbtLimits::default())
+    }
+
+    pub fn with_limits(inner: R, limits: NbtLimits) -> NbtStreamReader<R> {
+        NbtStreamReader {
+            inner,
+            position: 0,
+            accounter: NbtAccounter::new(limits),
+        }
     }
 
     #[inline]
-    fn read_long_array(r: &mut self::NbtReader) -> Result<Vec<i64>> {
-        Nbt::read_long_array(r)
+    pub fn position(&self) -> usize {
+        self.position
     }
 
     #[inline]
-    fn read_list(r: &mut self::NbtReader) -> Result<Vec<Value>> {
-        Nbt::read_list(r)
+    pub fn accounted(&self) -> u64 {
+        self.accounter.accounted()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
     }
 
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> NbtStreamReader<io::BufReader<R>> {
+    pub fn buffered(inner: R) -> NbtStreamReader<io::BufReader<R>> {
+        NbtStreamReader::new(io::BufReader::new(inner))
+    }
+}
+
+// Largest number of elements preallocated before the data has actually arrived.
+const STREAM_PREALLOCATE: usize = 4096;
+
+impl<R: io::Read> NbtSource for NbtStreamReader<R> {
     #[inline]
-    fn read_compound(r: &mut self::NbtReader) -> Result<Vec<(String, Value)>> {
-        Nbt::read_compound(r)
+    fn accounter(&mut self) -> &mut NbtAccounter {
+        &mut self.accounter
     }
 
-    fn from_reader(r: &mut self::NbtReader) -> Result<Value> {
-        match r.read_u8() {
-            10 => {
-                Ok(Value::Compound(None, Nbt::read_compound(r)?))
+    #[inline]
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        match self.inner.read_exact(&mut bytes) {
+            Ok(()) => {
+                self.position += N;
+                Ok(bytes)
             }
-            x => Err(Error::RootTagNotCompound(x)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(Error::UnexpectedEof { offset: self.position, needed: N })
+            }
+            Err(e) => Err(Error::Io(e)),
         }
     }
-}
\ No newline at end of file
+
+    fn read_bytes(&mut self, len: usize) -> Result<Cow<'_, [u8]>> {
+        // `take` grows the buffer as data arrives instead of trusting `len` up front
+        let mut bytes = Vec::with_capacity(len.min(STREAM_PREALLOCATE));
+        if let Err(e) = io::Read::take(&mut self.inner, len as u64).read_to_end(&mut bytes) {
+            return Err(Error::Io(e));
+        }
+        if bytes.len() < len {
+            return Err(Error::UnexpectedEof { offset: self.position, needed: len });
+        }
+        self.position += len;
+        Ok(Cow::Owned(bytes))
+    }
+
+    #[inline]
+    fn capacity_hint(&self, len: usize, _size: usize) -> usize {
+        len.min(STREAM_PREALLOCATE)
+    }
+}
@@ -0,0 +1,299 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::compression::{read_decompressed, write_compressed};
+use crate::{Compression, Error, Nbt, Result, Value};
+
+pub const SECTOR_SIZE: usize = 4096;
+pub const CHUNKS: usize = 1024;
+// The sector count is a single byte in the location table.
+const MAX_SECTORS: usize = 255;
+const EXTERNAL_FLAG: u8 = 0x80;
+const HEADER_SECTORS: usize = 2;
+const CHUNK_HEADER_LEN: usize = 5;
+
+fn compression_id(compression: Compression) -> u8 {
+    match compression {
+        Compression::Gzip => 1,
+        Compression::Zlib => 2,
+        Compression::None => 3,
+        Compression::Lz4 => 4,
+    }
+}
+
+fn compression_from_id(id: u8) -> Result<Compression> {
+    match id {
+        1 => Ok(Compression::Gzip),
+        2 => Ok(Compression::Zlib),
+        3 => Ok(Compression::None),
+        4 => Ok(Compression::Lz4),
+        x => Err(Error::Region(format!("Unknown chunk compression type: {}", x))),
+    }
+}
+
+fn sectors_for(len: usize) -> usize {
+    (len + CHUNK_HEADER_LEN).div_ceil(SECTOR_SIZE)
+}
+
+fn index(x: usize, z: usize) -> Result<usize> {
+    if x >= 32 || z >= 32 {
+        return Err(Error::Region(format!("Chunk ({}, {}) is outside the region", x, z)));
+    }
+    Ok(x + z * 32)
+}
+
+// The sector offset has 24 bits in the location table.
+fn location(offset: usize, count: usize) -> Result<u32> {
+    if offset >= 1 << 24 {
+        return Err(Error::Region(format!("Sector offset {} does not fit in the location table", offset)));
+    }
+    Ok(((offset << 8) | count) as u32)
+}
+
+fn now() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as u32)
+}
+
+/// An Anvil region file (`r.<x>.<z>.mca`) holding 32x32 chunks.
+///
+/// Chunk coordinates are local to the region. Chunks too large for the region file are
+/// stored next to it in `c.<x>.<z>.mcc`, named after their absolute chunk coordinates.
+pub struct Region {
+    file: File,
+    dir: PathBuf,
+    position: Option<(i32, i32)>,
+    locations: Vec<u32>,
+    timestamps: Vec<u32>,
+    used: Vec<bool>,
+}
+
+impl Region {
+    /// Opens a region file, creating it with an empty header if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Region> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path).map_err(Error::Io)?;
+
+        let len = file.metadata().map_err(Error::Io)?.len() as usize;
+        let mut header = vec![0u8; HEADER_SECTORS * SECTOR_SIZE];
+        if len == 0 {
+            file.set_len(header.len() as u64).map_err(Error::Io)?;
+        } else if len < header.len() {
+            return Err(Error::Region(format!("Truncated header of {} bytes", len)));
+        } else {
+            file.read_exact(&mut header).map_err(Error::Io)?;
+        }
+        let word = |i: usize| u32::from_be_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+        let locations: Vec<u32> = (0..CHUNKS).map(word).collect();
+        let timestamps = (CHUNKS..2 * CHUNKS).map(word).collect();
+
+        let mut used = vec![false; len.max(header.len()).div_ceil(SECTOR_SIZE)];
+        used[..HEADER_SECTORS].fill(true);
+        for &location in &locations {
+            let (offset, count) = ((location >> 8) as usize, (location & 0xff) as usize);
+            if offset >= HEADER_SECTORS && offset + count <= used.len() {
+                used[offset..offset + count].fill(true);
+            }
+        }
+
+        let position = path.file_name().and_then(|name| name.to_str()).and_then(|name| {
+            let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+            let x = parts.next()?.parse().ok()?;
+            let z = parts.next()?.parse().ok()?;
+            parts.next().is_none().then_some((x, z))
+        });
+        let dir = path.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+
+        Ok(Region { file, dir, position, locations, timestamps, used })
+    }
+
+    /// The region coordinates parsed from the file name, if it follows the `r.<x>.<z>.mca` pattern.
+    pub fn position(&self) -> Option<(i32, i32)> {
+        self.position
+    }
+
+    pub fn contains(&self, x: usize, z: usize) -> Result<bool> {
+        Ok(self.locations[index(x, z)?] != 0)
+    }
+
+    /// Last modification time of the chunk in seconds since the Unix epoch.
+    pub fn timestamp(&self, x: usize, z: usize) -> Result<u32> {
+        Ok(self.timestamps[index(x, z)?])
+    }
+
+    /// Local coordinates of every chunk present in the region.
+    pub fn chunks(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..CHUNKS).filter(|&i| self.locations[i] != 0).map(|i| (i % 32, i / 32))
+    }
+
+    pub fn read_chunk(&mut self, x: usize, z: usize) -> Result<Option<Value>> {
+        match self.read_raw(x, z)? {
+            Some((compression, data)) => Ok(Some(read_decompressed::<Nbt, _>(&data[..], compression)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the chunk's compression and its still compressed data.
+    pub fn read_raw(&mut self, x: usize, z: usize) -> Result<Option<(Compression, Vec<u8>)>> {
+        let Some((id, data)) = self.read_stored(index(x, z)?)? else {
+            return Ok(None);
+        };
+        let compression = compression_from_id(id & !EXTERNAL_FLAG)?;
+        if id & EXTERNAL_FLAG != 0 {
+            let path = self.external_path(x, z)?;
+            return Ok(Some((compression, fs::read(path).map_err(Error::Io)?)));
+        }
+        Ok(Some((compression, data)))
+    }
+
+    /// Writes the chunk with zlib compression, the default of the vanilla server.
+    pub fn write_chunk(&mut self, x: usize, z: usize, value: &Value) -> Result<()> {
+        self.write_chunk_with(x, z, value, Compression::Zlib)
+    }
+
+    pub fn write_chunk_with(&mut self, x: usize, z: usize, value: &Value, compression: Compression) -> Result<()> {
+        let data = write_compressed::<Nbt, _>(Vec::new(), value, compression)?;
+        self.write_raw(x, z, compression, &data)
+    }
+
+    /// Stores already compressed chunk data and updates the chunk's timestamp.
+    pub fn write_raw(&mut self, x: usize, z: usize, compression: Compression, data: &[u8]) -> Result<()> {
+        let i = index(x, z)?;
+        let external = self.external_path(x, z);
+        let (id, data, stale) = if sectors_for(data.len()) > MAX_SECTORS {
+            fs::write(external?, data).map_err(Error::Io)?;
+            (compression_id(compression) | EXTERNAL_FLAG, &[][..], None)
+        } else {
+            (compression_id(compression), data, external.ok().filter(|path| path.exists()))
+        };
+
+        // the new copy is in place before the old sectors are released
+        let old = self.locations[i];
+        let count = sectors_for(data.len());
+        let offset = self.allocate(count);
+        self.write_sectors(offset, id, data)?;
+        self.set_location(i, location(offset, count)?, now())?;
+        self.release(old);
+
+        if let Some(path) = stale {
+            fs::remove_file(path).map_err(Error::Io)?;
+        }
+        Ok(())
+    }
+
+    pub fn remove_chunk(&mut self, x: usize, z: usize) -> Result<()> {
+        let i = index(x, z)?;
+        let old = self.locations[i];
+        if old == 0 {
+            return Ok(());
+        }
+        self.set_location(i, 0, 0)?;
+        self.release(old);
+        if let Ok(path) = self.external_path(x, z) {
+            if path.exists() {
+                fs::remove_file(path).map_err(Error::Io)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves every chunk towards the start of the file, closing the gaps left by chunks
+    /// that were removed or moved, and truncates the file. Timestamps are kept.
+    pub fn defragment(&mut self) -> Result<()> {
+        let mut order: Vec<usize> = (0..CHUNKS).filter(|&i| self.locations[i] != 0).collect();
+        order.sort_by_key(|&i| self.locations[i] >> 8);
+
+        let mut chunks = Vec::with_capacity(order.len());
+        for &i in &order {
+            let (id, data) = self.read_stored(i)?.ok_or_else(|| Error::Region(format!("Chunk {} disappeared while defragmenting", i)))?;
+            chunks.push((i, id, data));
+        }
+
+        self.used = vec![true; HEADER_SECTORS];
+        for (i, id, data) in chunks {
+            let count = sectors_for(data.len());
+            let offset = self.allocate(count);
+            self.write_sectors(offset, id, &data)?;
+            self.set_location(i, location(offset, count)?, self.timestamps[i])?;
+        }
+        self.file.set_len((self.used.len() * SECTOR_SIZE) as u64).map_err(Error::Io)
+    }
+
+    /// The number of sectors in the file, including the two header sectors.
+    pub fn sectors(&self) -> usize {
+        self.used.len()
+    }
+
+    fn external_path(&self, x: usize, z: usize) -> Result<PathBuf> {
+        let (rx, rz) = self.position.ok_or_else(|| {
+            Error::Region("External chunks need the region file to be named r.<x>.<z>.mca".to_string())
+        })?;
+        Ok(self.dir.join(format!("c.{}.{}.mcc", rx * 32 + x as i32, rz * 32 + z as i32)))
+    }
+
+    // Returns the stored compression byte and the data that follows it in the region file.
+    fn read_stored(&mut self, i: usize) -> Result<Option<(u8, Vec<u8>)>> {
+        let location = self.locations[i];
+        if location == 0 {
+            return Ok(None);
+        }
+        let (offset, count) = ((location >> 8) as usize, (location & 0xff) as usize);
+        if offset < HEADER_SECTORS || offset + count > self.used.len() {
+            return Err(Error::Region(format!("Chunk {} points outside the file at sector {}", i, offset)));
+        }
+
+        let mut header = [0u8; CHUNK_HEADER_LEN];
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64)).map_err(Error::Io)?;
+        self.file.read_exact(&mut header).map_err(Error::Io)?;
+        let len = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        if len == 0 || len + 4 > count * SECTOR_SIZE {
+            return Err(Error::Region(format!("Chunk {} has invalid length {} for {} sectors", i, len, count)));
+        }
+        let mut data = vec![0u8; len - 1];
+        self.file.read_exact(&mut data).map_err(Error::Io)?;
+        Ok(Some((header[4], data)))
+    }
+
+    fn write_sectors(&mut self, offset: usize, id: u8, data: &[u8]) -> Result<()> {
+        let mut buf = Vec::with_capacity(sectors_for(data.len()) * SECTOR_SIZE);
+        buf.extend_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+        buf.push(id);
+        buf.extend_from_slice(data);
+        buf.resize(sectors_for(data.len()) * SECTOR_SIZE, 0);
+        self.file.seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64)).map_err(Error::Io)?;
+        self.file.write_all(&buf).map_err(Error::Io)
+    }
+
+    fn set_location(&mut self, i: usize, location: u32, timestamp: u32) -> Result<()> {
+        self.locations[i] = location;
+        self.timestamps[i] = timestamp;
+        self.file.seek(SeekFrom::Start((i * 4) as u64)).map_err(Error::Io)?;
+        self.file.write_all(&location.to_be_bytes()).map_err(Error::Io)?;
+        self.file.seek(SeekFrom::Start((SECTOR_SIZE + i * 4) as u64)).map_err(Error::Io)?;
+        self.file.write_all(&timestamp.to_be_bytes()).map_err(Error::Io)
+    }
+
+    // First fit, growing the file when no gap is large enough.
+    fn allocate(&mut self, count: usize) -> usize {
+        let mut start = HEADER_SECTORS;
+        while start < self.used.len() {
+            match self.used[start..].iter().take(count).position(|&used| used) {
+                Some(n) => start += n + 1,
+                None => break,
+            }
+        }
+        if self.used.len() < start + count {
+            self.used.resize(start + count, false);
+        }
+        self.used[start..start + count].fill(true);
+        start
+    }
+
+    fn release(&mut self, location: u32) {
+        let (offset, count) = ((location >> 8) as usize, (location & 0xff) as usize);
+        if offset >= HEADER_SECTORS && offset + count <= self.used.len() {
+            self.used[offset..offset + count].fill(false);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use serde::ser::{self, Serialize};
+
+use crate::{Compound, Error, Result, Value};
+
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    value.serialize(Serializer)
+}
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Error {
+        Error::Custom(msg.to_string())
+    }
+}
+
+// Newtype names the array helpers mark their sequences with; other serializers see plain sequences.
+const BYTE_ARRAY: &str = "__nbt_byte_array";
+const INT_ARRAY: &str = "__nbt_int_array";
+const LONG_ARRAY: &str = "__nbt_long_array";
+
+/// Serializes bytes as a byte array tag, for use with `#[serde(serialize_with = "nbt::ser::byte_array")]`.
+pub fn byte_array<S: ser::Serializer>(values: &[i8], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_newtype_struct(BYTE_ARRAY, values)
+}
+
+/// Serializes ints as an int array tag, like [`byte_array`].
+pub fn int_array<S: ser::Serializer>(values: &[i32], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_newtype_struct(INT_ARRAY, values)
+}
+
+/// Serializes longs as a long array tag, like [`byte_array`].
+pub fn long_array<S: ser::Serializer>(values: &[i64], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_newtype_struct(LONG_ARRAY, values)
+}
+
+fn array<T>(value: Value, tag: u8, get: impl Fn(&Value) -> Option<T>) -> Result<Vec<T>> {
+    let Value::List(values) = value else { return Err(Error::TagMismatch { expected: 9, found: value.tag() }) };
+    values.iter().map(|v| get(v).ok_or(Error::TagMismatch { expected: tag, found: v.tag() })).collect()
+}
+
+/// Serializes into a [`Value`].
+///
+/// Sequences and tuples become lists whatever their elements, as vanilla writes them. The array
+/// tags are only written for fields marked with [`byte_array`], [`int_array`] or [`long_array`],
+/// and for byte strings such as `serde_bytes` produces.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeCompound;
+    type SerializeStruct = SerializeCompound;
+    type SerializeStructVariant = SerializeCompound;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Byte(v as i8))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(Value::Byte(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(Value::Short(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(Value::Int(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::Long(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(Value::Byte(v as i8))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(Value::Short(v as i16))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(Value::Int(v as i32))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        Ok(Value::Long(v as i64))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::ByteArray(v.iter().map(|&b| b as i8).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Err(Error::UnsupportedNone)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Compound(Compound::new()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Value> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, name: &'static str, value: &T) -> Result<Value> {
+        let value = value.serialize(self)?;
+        match name {
+            BYTE_ARRAY => Ok(Value::ByteArray(array(value, 1, |v| match v { Value::Byte(v) => Some(*v), _ => None })?)),
+            INT_ARRAY => Ok(Value::IntArray(array(value, 3, |v| match v { Value::Int(v) => Some(*v), _ => None })?)),
+            LONG_ARRAY => Ok(Value::LongArray(array(value, 4, |v| match v { Value::Long(v) => Some(*v), _ => None })?)),
+            _ => Ok(value),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        Ok(Value::Compound([(variant.to_string(), value.serialize(Serializer)?)].into_iter().collect()))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList { variant: None, values: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList> {
+        Ok(SerializeList { variant: Some(variant), values: Vec::with_capacity(len) })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeCompound> {
+        Ok(SerializeCompound { variant: None, key: None, entries: Compound::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeCompound> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeCompound> {
+        Ok(SerializeCompound { variant: Some(variant), key: None, entries: Compound::with_capacity(len) })
+    }
+}
+
+pub struct SerializeList {
+    variant: Option<&'static str>,
+    values: Vec<Value>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        match value.serialize(Serializer) {
+            Ok(value) => self.values.push(value),
+            Err(Error::UnsupportedNone) => return Err(Error::Custom("cannot serialize None inside a list".to_string())),
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        let value = Value::List(self.values);
+        match self.variant {
+            Some(variant) => Ok(Value::Compound([(variant.to_string(), value)].into_iter().collect())),
+            None => Ok(value),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+pub struct SerializeCompound {
+    variant: Option<&'static str>,
+    key: Option<String>,
+    entries: Compound,
+}
+
+impl SerializeCompound {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        match value.serialize(Serializer) {
+            Ok(value) => {
+                self.entries.insert(key, value);
+            }
+            // `None` fields are left out of the compound
+            Err(Error::UnsupportedNone) => {}
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        let value = Value::Compound(self.entries);
+        match self.variant {
+            Some(variant) => Ok(Value::Compound([(variant.to_string(), value)].into_iter().collect())),
+            None => Ok(value),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeCompound {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match key.serialize(Serializer)? {
+            Value::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            x => Err(Error::Custom(format!("compound keys must be strings, got tag {}", x.tag()))),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeCompound {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeCompound {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
@@ -0,0 +1,452 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use crate::reader::NbtLimits;
+use crate::{Compound, Error, Result, Value};
+
+pub fn from_str(s: &str) -> Result<Value> {
+    let mut parser = Parser { s, cursor: 0, depth: 0 };
+    let value = parser.read_value()?;
+    parser.skip_whitespace();
+    if parser.cursor < s.len() {
+        return Err(parser.error("Unexpected trailing data"));
+    }
+    Ok(value)
+}
+
+// Parses the compound at the start of `s`, returning it and the number of bytes it took up.
+pub(crate) fn read_compound_prefix(s: &str) -> Result<(Value, usize)> {
+    let mut parser = Parser { s, cursor: 0, depth: 0 };
+    let entries = parser.read_compound()?;
+    Ok((Value::Compound(entries), parser.cursor))
+}
+
+/// Reads the string quoted with `quote` at the start of `s`, returning it and the bytes consumed.
+pub(crate) fn read_quoted_prefix(s: &str, quote: char) -> Result<(String, usize)> {
+    let mut parser = Parser { s, cursor: 0, depth: 0 };
+    let value = parser.read_quoted(quote)?;
+    Ok((value, parser.cursor))
+}
+
+pub fn to_string(value: &Value) -> String {
+    let mut s = String::new();
+    write_value(&mut s, value).expect("writing to a String cannot fail");
+    s
+}
+
+/// Like [`to_string`], but with each compound entry, and each element of a list of lists or
+/// compounds, on its own line, indented by four spaces per level.
+pub fn to_string_pretty(value: &Value) -> String {
+    let mut s = String::new();
+    write_pretty(&mut s, value, 0).expect("writing to a String cannot fail");
+    s
+}
+
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Value> {
+        from_str(s)
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    cursor: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::Snbt { offset: self.cursor, message: message.to_string() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.cursor..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("Expected '{}'", expected)));
+        }
+        self.cursor += 1;
+        Ok(())
+    }
+
+    // Consumes a ',' if present; returns whether more elements may follow.
+    fn separator(&mut self) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(',') {
+            self.cursor += 1;
+            self.skip_whitespace();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn push_depth(&mut self) -> Result<()> {
+        if self.depth >= NbtLimits::MAX_DEPTH {
+            return Err(Error::DepthLimitExceeded(NbtLimits::MAX_DEPTH));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn read_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => Ok(Value::Compound(self.read_compound()?)),
+            Some('[') => self.read_list_or_array(),
+            Some(quote @ ('"' | '\'')) => Ok(Value::String(self.read_quoted(quote)?)),
+            Some(_) => {
+                let start = self.cursor;
+                let token = self.read_unquoted();
+                if token.is_empty() {
+                    self.cursor = start;
+                    return Err(self.error("Expected value"));
+                }
+                Ok(parse_primitive(token))
+            }
+            None => Err(self.error("Expected value")),
+        }
+    }
+
+    fn read_key(&mut self) -> Result<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => self.read_quoted(quote),
+            _ => {
+                let key = self.read_unquoted();
+                if key.is_empty() {
+                    return Err(self.error("Expected key"));
+                }
+                Ok(key.to_string())
+            }
+        }
+    }
+
+    fn read_unquoted(&mut self) -> &str {
+        let start = self.cursor;
+        while let Some(c) = self.peek() {
+            if !is_unquoted_char(c) {
+                break;
+            }
+            self.cursor += 1;
+        }
+        &self.s[start..self.cursor]
+    }
+
+    // Reads a string opened by `quote`, in which only `quote` and backslashes are escaped.
+    fn read_quoted(&mut self, quote: char) -> Result<String> {
+        self.cursor += quote.len_utf8();
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some('\\') => {
+                    self.cursor += 1;
+                    match self.peek() {
+                        Some(c) if c == '\\' || c == quote => {
+                            value.push(c);
+                            self.cursor += 1;
+                        }
+                        _ => return Err(self.error("Invalid escape sequence")),
+                    }
+                }
+                Some(c) if c == quote => {
+                    self.cursor += 1;
+                    return Ok(value);
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.cursor += c.len_utf8();
+                }
+                None => return Err(self.error("Unclosed quoted string")),
+            }
+        }
+    }
+
+    fn read_compound(&mut self) -> Result<Compound> {
+        self.push_depth()?;
+        self.expect('{')?;
+        let mut entries = Compound::new();
+        self.skip_whitespace();
+        while self.peek() != Some('}') {
+            let key = self.read_key()?;
+            self.expect(':')?;
+            let value = self.read_value()?;
+            entries.insert(key, value);
+            if !self.separator() {
+                break;
+            }
+        }
+        self.expect('}')?;
+        self.depth -= 1;
+        Ok(entries)
+    }
+
+    fn read_list_or_array(&mut self) -> Result<Value> {
+        let rest = &self.s.as_bytes()[self.cursor..];
+        if rest.len() >= 3 && rest[2] == b';' && !rest[1].is_ascii_whitespace() {
+            let kind = rest[1];
+            let start = self.cursor;
+            self.cursor += 3;
+            self.skip_whitespace();
+            return match kind {
+                b'B' => Ok(Value::ByteArray(self.read_array_elements(|v| match v {
+                    Value::Byte(v) => Some(v),
+                    _ => None,
+                })?)),
+                b'I' => Ok(Value::IntArray(self.read_array_elements(|v| match v {
+                    Value::Int(v) => Some(v),
+                    _ => None,
+                })?)),
+                b'L' => Ok(Value::LongArray(self.read_array_elements(|v| match v {
+                    Value::Long(v) => Some(v),
+                    _ => None,
+                })?)),
+                _ => {
+                    self.cursor = start + 1;
+                    Err(self.error("Invalid array type"))
+                }
+            };
+        }
+        self.push_depth()?;
+        self.expect('[')?;
+        let mut values: Vec<Value> = Vec::new();
+        self.skip_whitespace();
+        while self.peek() != Some(']') {
+            let start = self.cursor;
+            let value = self.read_value()?;
+            if values.first().is_some_and(|first| first.tag() != value.tag()) {
+                self.cursor = start;
+                return Err(self.error("Can't insert mixed types into a list"));
+            }
+            values.push(value);
+            if !self.separator() {
+                break;
+            }
+        }
+        self.expect(']')?;
+        self.depth -= 1;
+        Ok(Value::List(values))
+    }
+
+    fn read_array_elements<T>(&mut self, element: impl Fn(Value) -> Option<T>) -> Result<Vec<T>> {
+        let mut values = Vec::new();
+        while self.peek() != Some(']') {
+            let start = self.cursor;
+            match element(self.read_value()?) {
+                Some(v) => values.push(v),
+                None => {
+                    self.cursor = start;
+                    return Err(self.error("Invalid array element type"));
+                }
+            }
+            if !self.separator() {
+                break;
+            }
+        }
+        self.expect(']')?;
+        Ok(values)
+    }
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+// [-+]?(?:0|[1-9][0-9]*)
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    match digits.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+
+// [-+]?(?:[0-9]+[.]?|[0-9]*[.][0-9]+)(?:e[-+]?[0-9]+)?, with a mandatory '.' if `needs_dot`
+fn is_decimal(s: &str, needs_dot: bool) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    if let Some(exponent) = exponent {
+        let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+        if exponent.is_empty() || !exponent.bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+    }
+    let (int, frac) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], Some(&mantissa[i + 1..])),
+        None => (mantissa, None),
+    };
+    if !int.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    match frac {
+        Some(frac) => frac.bytes().all(|b| b.is_ascii_digit()) && !(int.is_empty() && frac.is_empty()),
+        None => !needs_dot && !int.is_empty(),
+    }
+}
+
+// Vanilla has no syntax for these, so Java's names for them are written with the usual suffix.
+// NaN payloads other than the default one are not kept.
+fn is_non_finite(body: &str) -> bool {
+    matches!(body, "NaN" | "Infinity" | "-Infinity")
+}
+
+fn non_finite_name(v: f64) -> Option<&'static str> {
+    match v {
+        v if v.is_nan() => Some("NaN"),
+        f64::INFINITY => Some("Infinity"),
+        f64::NEG_INFINITY => Some("-Infinity"),
+        _ => None,
+    }
+}
+
+// Mirrors vanilla's `TagParser.type`: anything that doesn't look like a number stays a string.
+fn parse_primitive(token: &str) -> Value {
+    if token.eq_ignore_ascii_case("true") {
+        return Value::Byte(1);
+    }
+    if token.eq_ignore_ascii_case("false") {
+        return Value::Byte(0);
+    }
+    let (body, suffix) = token.split_at(token.len() - 1);
+    let parsed = match suffix {
+        "b" | "B" if is_integer(body) => body.parse().ok().map(Value::Byte),
+        "s" | "S" if is_integer(body) => body.parse().ok().map(Value::Short),
+        "l" | "L" if is_integer(body) => body.parse().ok().map(Value::Long),
+        "f" | "F" if is_decimal(body, false) || is_non_finite(body) => body.parse().ok().map(Value::Float),
+        "d" | "D" if is_decimal(body, false) || is_non_finite(body) => body.parse().ok().map(Value::Double),
+        _ if is_integer(token) => token.parse().ok().map(Value::Int),
+        _ if is_decimal(token, true) => token.parse().ok().map(Value::Double),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(token.to_string()))
+}
+
+fn write_string<W: Write>(w: &mut W, value: &str) -> fmt::Result {
+    let quote = if value.contains('"') { '\'' } else { '"' };
+    w.write_char(quote)?;
+    for c in value.chars() {
+        if c == '\\' || c == quote {
+            w.write_char('\\')?;
+        }
+        w.write_char(c)?;
+    }
+    w.write_char(quote)
+}
+
+fn write_key<W: Write>(w: &mut W, key: &str) -> fmt::Result {
+    if !key.is_empty() && key.chars().all(is_unquoted_char) {
+        w.write_str(key)
+    } else {
+        write_string(w, key)
+    }
+}
+
+fn write_array<W: Write, T: fmt::Display>(w: &mut W, prefix: &str, suffix: &str, values: &[T]) -> fmt::Result {
+    w.write_str(prefix)?;
+    for (i, v) in values.iter().enumerate() {
+        if i > 0 {
+            w.write_char(',')?;
+        }
+        write!(w, "{}{}", v, suffix)?;
+    }
+    w.write_char(']')
+}
+
+pub fn write_value<W: Write>(w: &mut W, value: &Value) -> fmt::Result {
+    match value {
+        Value::Byte(v) => write!(w, "{}b", v),
+        Value::Short(v) => write!(w, "{}s", v),
+        Value::Int(v) => write!(w, "{}", v),
+        Value::Long(v) => write!(w, "{}L", v),
+        Value::Float(v) => match non_finite_name(*v as f64) {
+            Some(name) => write!(w, "{}f", name),
+            None => write!(w, "{:?}f", v),
+        },
+        Value::Double(v) => match non_finite_name(*v) {
+            Some(name) => write!(w, "{}d", name),
+            None => write!(w, "{:?}d", v),
+        },
+        Value::ByteArray(v) => write_array(w, "[B;", "B", v),
+        Value::String(v) => write_string(w, v),
+        Value::List(v) => {
+            w.write_char('[')?;
+            for (i, v) in v.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_value(w, v)?;
+            }
+            w.write_char(']')
+        }
+        Value::Compound(v) => {
+            w.write_char('{')?;
+            for (i, (key, v)) in v.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_key(w, key)?;
+                w.write_char(':')?;
+                write_value(w, v)?;
+            }
+            w.write_char('}')
+        }
+        Value::IntArray(v) => write_array(w, "[I;", "", v),
+        Value::LongArray(v) => write_array(w, "[L;", "L", v),
+    }
+}
+
+fn write_indent<W: Write>(w: &mut W, depth: usize) -> fmt::Result {
+    (0..depth).try_for_each(|_| w.write_str("    "))
+}
+
+pub fn write_pretty<W: Write>(w: &mut W, value: &Value, depth: usize) -> fmt::Result {
+    match value {
+        Value::List(v) if v.first().is_some_and(|v| matches!(v, Value::List(_) | Value::Compound(_))) => {
+            w.write_str("[\n")?;
+            for (i, v) in v.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(",\n")?;
+                }
+                write_indent(w, depth + 1)?;
+                write_pretty(w, v, depth + 1)?;
+            }
+            w.write_char('\n')?;
+            write_indent(w, depth)?;
+            w.write_char(']')
+        }
+        Value::Compound(v) if !v.is_empty() => {
+            w.write_str("{\n")?;
+            for (i, (key, v)) in v.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(",\n")?;
+                }
+                write_indent(w, depth + 1)?;
+                write_key(w, key)?;
+                w.write_str(": ")?;
+                write_pretty(w, v, depth + 1)?;
+            }
+            w.write_char('\n')?;
+            write_indent(w, depth)?;
+            w.write_char('}')
+        }
+        v => write_value(w, v),
+    }
+}
@@ -0,0 +1,106 @@
+use std::{fs, io::Read};
+
+use flate2::read::GzDecoder;
+
+use crate::{
+    bedrock::{read_all, read_level_dat, write_level_dat},
+    reader::NbtReader, snbt, writer::NbtWriter, BedrockNbt, BedrockNetworkNbt, Error, NamedTag, Nbt, NbtReadTrait,
+    NbtWriteTrait, Value,
+};
+
+fn encode<D: NbtWriteTrait>(value: &Value) -> Vec<u8> {
+    let mut w = NbtWriter::new();
+    D::write_to(&mut w, value).unwrap();
+    w.data
+}
+
+fn decode<D: NbtReadTrait>(data: &[u8]) -> Value {
+    D::from_reader(&mut NbtReader::new(data)).unwrap()
+}
+
+fn encode_named<D: NbtWriteTrait>(tag: &NamedTag) -> Vec<u8> {
+    let mut w = NbtWriter::new();
+    D::write_named(&mut w, tag).unwrap();
+    w.data
+}
+
+fn decode_named<D: NbtReadTrait>(data: &[u8]) -> NamedTag {
+    D::from_reader_named(&mut NbtReader::new(data)).unwrap()
+}
+
+#[test]
+fn test_bedrock_encoding() {
+    let value = snbt::from_str("{a:300,b:-1L,c:[1s],d:\"hé\"}").unwrap();
+    #[rustfmt::skip]
+    let le = [
+        10, 0, 0,
+        3, 1, 0, b'a', 0x2c, 1, 0, 0,
+        4, 1, 0, b'b', 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        9, 1, 0, b'c', 2, 1, 0, 0, 0, 1, 0,
+        8, 1, 0, b'd', 3, 0, b'h', 0xc3, 0xa9,
+        0,
+    ];
+    #[rustfmt::skip]
+    let network = [
+        10, 0,
+        3, 1, b'a', 0xd8, 0x04,
+        4, 1, b'b', 1,
+        9, 1, b'c', 2, 2, 1, 0,
+        8, 1, b'd', 3, b'h', 0xc3, 0xa9,
+        0,
+    ];
+    assert_eq!(encode::<BedrockNbt>(&value), le);
+    assert_eq!(encode::<BedrockNetworkNbt>(&value), network);
+    assert_eq!(snbt::to_string(&decode::<BedrockNbt>(&le)), snbt::to_string(&value));
+    assert_eq!(snbt::to_string(&decode::<BedrockNetworkNbt>(&network)), snbt::to_string(&value));
+}
+
+#[test]
+fn test_bedrock_conversions() {
+    let mut java = Vec::new();
+    GzDecoder::new(&fs::read("src/test/bigtest.nbt").unwrap()[..]).read_to_end(&mut java).unwrap();
+
+    let tag = decode_named::<Nbt>(&java);
+    let le = encode_named::<BedrockNbt>(&tag);
+    let network = encode_named::<BedrockNetworkNbt>(&decode_named::<BedrockNbt>(&le));
+    assert_eq!(encode_named::<Nbt>(&decode_named::<BedrockNetworkNbt>(&network)), java);
+    assert_eq!(encode_named::<BedrockNbt>(&decode_named::<BedrockNetworkNbt>(&network)), le);
+    assert_eq!(encode_named::<BedrockNetworkNbt>(&decode_named::<Nbt>(&java)), network);
+    assert!(network.len() < le.len());
+
+    let extremes = snbt::from_str("{a:2147483647,b:-2147483648,c:9223372036854775807L,d:-9223372036854775808L,e:[I;0,-1,1],f:[L;-1L]}").unwrap();
+    let network = encode::<BedrockNetworkNbt>(&extremes);
+    assert_eq!(snbt::to_string(&decode::<BedrockNetworkNbt>(&network)), snbt::to_string(&extremes));
+}
+
+#[test]
+fn test_bedrock_invalid() {
+    // an 11 byte VarInt
+    let data = [10, 0, 4, 1, b'a', 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 1, 0];
+    assert!(matches!(BedrockNetworkNbt::from_reader(&mut NbtReader::new(&data)), Err(Error::VarIntTooLong(10))));
+    let data = [10, 0, 0, 8, 1, 0, b'a', 1, 0, 0xff, 0];
+    assert!(matches!(BedrockNbt::from_reader(&mut NbtReader::new(&data)), Err(Error::Utf8(_))));
+    assert!(matches!(BedrockNbt::from_reader_ref(&mut NbtReader::new(&data)), Err(Error::Unsupported(_))));
+
+    let mut deep = vec![10, 0, 9, 1, b'a', 9, 2];
+    for _ in 0..1000 {
+        deep.extend_from_slice(&[9, 2]);
+    }
+    assert!(matches!(BedrockNetworkNbt::from_reader(&mut NbtReader::new(&deep)), Err(Error::DepthLimitExceeded(512))));
+}
+
+#[test]
+fn test_level_dat() {
+    let value = snbt::from_str("{LevelName:\"My World\",StorageVersion:10,RandomSeed:42L}").unwrap();
+    let data = write_level_dat(10, &value).unwrap();
+    assert_eq!(data[..4], 10i32.to_le_bytes());
+    assert_eq!(data[4..8], ((data.len() - 8) as u32).to_le_bytes());
+    let (version, read) = read_level_dat(&data).unwrap();
+    assert_eq!(version, 10);
+    assert_eq!(snbt::to_string(&read), snbt::to_string(&value));
+    assert!(matches!(read_level_dat(&data[..data.len() - 1]), Err(Error::UnexpectedEof { .. })));
+
+    let mut concatenated = encode::<BedrockNbt>(&value);
+    concatenated.extend(encode::<BedrockNbt>(&snbt::from_str("{id:\"Chest\"}").unwrap()));
+    assert_eq!(read_all(&concatenated).unwrap().len(), 2);
+}
@@ -0,0 +1,168 @@
+use crate::{
+    chunk::{pack, unpack, Packing, PalettedContainer, SECTIONS_DATA_VERSION},
+    snbt, BlockState, Chunk, Error, Value,
+};
+
+#[test]
+fn test_packing() {
+    let values: Vec<u16> = (0..16).collect();
+    assert_eq!(pack(&values, 4, Packing::Padded), vec![0xfedc_ba98_7654_3210u64 as i64]);
+
+    // with 5 bits the 13th entry spans the first two longs unless each long is padded
+    let mut values = vec![0u16; 13];
+    values[12] = 31;
+    assert_eq!(pack(&values, 5, Packing::Spanning), vec![0xf << 60, 1]);
+    assert_eq!(pack(&values, 5, Packing::Padded), vec![0, 31]);
+    assert_eq!(Packing::Spanning.longs(4096, 5), 320);
+    assert_eq!(Packing::Padded.longs(4096, 5), 342);
+
+    for bits in 1..=16 {
+        let values: Vec<u16> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 7) as u16 & ((1u32 << bits) - 1) as u16).collect();
+        for packing in [Packing::Spanning, Packing::Padded] {
+            let data = pack(&values, bits, packing);
+            assert_eq!(data.len(), packing.longs(4096, bits));
+            assert_eq!(unpack(&data, bits, 4096, packing).unwrap(), values, "{} bits {:?}", bits, packing);
+        }
+    }
+    assert!(matches!(unpack(&[0; 3], 4, 64, Packing::Padded), Err(Error::Chunk(_))));
+}
+
+fn chunk(data_version: i32) -> Value {
+    snbt::from_str(&format!(
+        r#"{{DataVersion:{}, xPos:3, zPos:-2, yPos:-4, Status:"minecraft:full", sections:[
+            {{Y:1b, block_states:{{palette:[{{Name:"minecraft:stone"}}]}}, biomes:{{palette:["minecraft:plains"]}}}},
+            {{Y:-5b, SkyLight:[B;1b,2b]}}
+        ]}}"#,
+        data_version
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_chunk_blocks() {
+    let mut chunk = Chunk::from_value(chunk(3465)).unwrap();
+    assert_eq!((chunk.x, chunk.z), (3, -2));
+    assert_eq!(chunk.sections.iter().map(|s| s.y).collect::<Vec<_>>(), vec![-5, 1]);
+    assert_eq!(chunk.get_block(0, 16, 0), Some(&BlockState::new("minecraft:stone")));
+    assert_eq!(chunk.get_block(15, 31, 15), Some(&BlockState::new("minecraft:stone")));
+    assert_eq!(chunk.get_block(0, -80, 0), None);
+    assert_eq!(chunk.get_block(0, 0, 0), None);
+    assert_eq!(chunk.get_biome(7, 20, 9), Some("minecraft:plains"));
+
+    let grass = BlockState::new("minecraft:grass_block").with("snowy", "false");
+    assert_eq!(chunk.set_block(1, 18, 3, grass.clone()).unwrap(), BlockState::new("minecraft:stone"));
+    assert_eq!(chunk.get_block(1, 18, 3), Some(&grass));
+    assert_eq!(chunk.get_block(1, 19, 3), Some(&BlockState::new("minecraft:stone")));
+    // a missing section is added and filled with air
+    assert_eq!(chunk.set_block(4, -1, 4, grass.clone()).unwrap(), BlockState::air());
+    assert_eq!(chunk.get_block(4, -1, 4), Some(&grass));
+    assert_eq!(chunk.get_block(4, -2, 4), Some(&BlockState::air()));
+    assert_eq!(chunk.sections.iter().map(|s| s.y).collect::<Vec<_>>(), vec![-5, -1, 1]);
+
+    // coordinates outside the chunk are not found, or rejected
+    assert_eq!(chunk.get_block(16, 18, 0), None);
+    assert_eq!(chunk.get_biome(0, 18, 99), None);
+    assert!(matches!(chunk.set_block(0, 18, 16, grass.clone()), Err(Error::Chunk(_))));
+    assert!(matches!(chunk.set_block(0, 2048, 0, grass.clone()), Err(Error::Chunk(_))));
+    assert_eq!(chunk.sections.len(), 3);
+
+    let value = chunk.to_value();
+    assert_eq!(value.get("Status"), Some(&Value::String("minecraft:full".to_string())));
+    let section = &value.get("sections").unwrap().as_list().unwrap()[2];
+    let blocks = section.get("block_states").unwrap();
+    assert_eq!(snbt::to_string(blocks.get("palette").unwrap()), r#"[{Name:"minecraft:stone"},{Name:"minecraft:grass_block",Properties:{snowy:"false"}}]"#);
+    assert_eq!(blocks.get("data").unwrap().as_long_array().unwrap().len(), 256);
+    assert_eq!(Chunk::from_value(value).unwrap(), chunk);
+
+    // palettes shrink to the values still in use when saved
+    chunk.set_block(1, 18, 3, BlockState::new("minecraft:stone")).unwrap();
+    let value = chunk.to_value();
+    let section = &value.get("sections").unwrap().as_list().unwrap()[2];
+    assert_eq!(snbt::to_string(section), r#"{Y:1b,block_states:{palette:[{Name:"minecraft:stone"}]},biomes:{palette:["minecraft:plains"]}}"#);
+    assert_eq!(Chunk::from_value(value.clone()).unwrap().to_value(), value);
+}
+
+// The layout before 1.18, nested in `Level`, with a palette only for sections that have blocks.
+fn legacy_chunk(data_version: i32) -> Value {
+    snbt::from_str(&format!(
+        r#"{{DataVersion:{}, Level:{{xPos:3, zPos:-2, Status:"full", Biomes:[I;1,1,1], Sections:[
+            {{Y:-1b, SkyLight:[B;1b,2b]}},
+            {{Y:1b, Palette:[{{Name:"minecraft:stone"}}], BlockStates:[L;{}]}}
+        ]}}}}"#,
+        data_version,
+        vec!["0L"; 256].join(",")
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_legacy_chunk() {
+    let value = legacy_chunk(2230);
+    let chunk = Chunk::from_value(value.clone()).unwrap();
+    assert_eq!((chunk.x, chunk.z), (3, -2));
+    assert_eq!(chunk.sections.iter().map(|s| s.y).collect::<Vec<_>>(), vec![-1, 1]);
+    assert_eq!(chunk.get_block(5, 20, 5), Some(&BlockState::new("minecraft:stone")));
+    assert_eq!(chunk.get_block(5, -10, 5), None);
+    assert_eq!(chunk.get_biome(5, 20, 5), None);
+    let level = chunk.other.get("Level").unwrap();
+    assert_eq!(level.get("Biomes"), Some(&Value::IntArray(vec![1, 1, 1])));
+    assert_eq!(level.get("Sections"), None);
+    // a single block state is still stored with four bits per block
+    assert_eq!(chunk.to_value(), value);
+
+    assert!(matches!(Chunk::from_value(snbt::from_str("{DataVersion:2230, xPos:0, zPos:0, sections:[]}").unwrap()), Err(Error::MissingField(k)) if k == "Level"));
+}
+
+#[test]
+fn test_chunk_packing() {
+    // 17 block states need 5 bits, which only pack differently before 1.16
+    for (value, longs) in [(legacy_chunk(2230), 320), (legacy_chunk(2586), 342), (chunk(3465), 342)] {
+        let mut chunk = Chunk::from_value(value).unwrap();
+        for i in 0..16 {
+            chunk.set_block(i, 16 + i as i32, 0, BlockState::new(format!("minecraft:block_{}", i))).unwrap();
+        }
+        let value = chunk.to_value();
+        let section = if chunk.data_version < SECTIONS_DATA_VERSION {
+            let section = &value.get("Level").unwrap().get("Sections").unwrap().as_list().unwrap()[1];
+            assert_eq!(section.get("Palette").unwrap().as_list().unwrap().len(), 17);
+            section.get("BlockStates").unwrap()
+        } else {
+            value.get("sections").unwrap().as_list().unwrap()[1].get("block_states").unwrap().get("data").unwrap()
+        };
+        let data = section.as_long_array().unwrap();
+        assert_eq!(data.len(), longs);
+        // block_9 sits at y 9 of its section and is the 11th palette entry, after stone
+        let packing = Packing::for_data_version(chunk.data_version);
+        assert_eq!(unpack(data, 5, 4096, packing).unwrap()[9 << 8 | 9], 10);
+
+        let read = Chunk::from_value(value).unwrap();
+        assert_eq!(read.get_block(9, 25, 0), Some(&BlockState::new("minecraft:block_9")));
+        assert_eq!(read, chunk);
+    }
+}
+
+#[test]
+fn test_paletted_container() {
+    let mut biomes = PalettedContainer::filled("minecraft:plains".to_string(), 64);
+    for i in 0..1000 {
+        biomes.set(i % 64, format!("minecraft:biome_{}", i));
+    }
+    // replaced values are dropped before the palette outgrows the entries
+    assert!(biomes.palette().len() <= 65);
+    assert_eq!(biomes.get(63), "minecraft:biome_959");
+    biomes.compact();
+    assert_eq!(biomes.palette().len(), 64);
+
+    let value = biomes.to_value(1, Packing::Padded, |b| Value::String(b.clone()));
+    let read = PalettedContainer::from_value(&value, 64, 1, Packing::Padded, |v| Ok(v.as_str().unwrap().to_string())).unwrap();
+    assert_eq!(read, biomes);
+
+    let bad = snbt::from_str("{palette:[\"a\",\"b\",\"c\"], data:[L;3L]}").unwrap();
+    let entry = |v: &Value| Ok(v.as_str().unwrap().to_string());
+    assert!(matches!(PalettedContainer::from_value(&bad, 64, 1, Packing::Padded, entry), Err(Error::Chunk(_))));
+    let bad = snbt::from_str("{palette:[\"a\",\"b\",\"c\"], data:[L;3L,0L,0L,0L]}").unwrap();
+    assert!(matches!(PalettedContainer::from_value(&bad, 64, 1, Packing::Padded, entry), Err(Error::Chunk(_))));
+    let bad = snbt::from_str("{palette:[\"a\",\"b\"]}").unwrap();
+    assert!(matches!(PalettedContainer::from_value(&bad, 64, 1, Packing::Padded, entry), Err(Error::MissingField(_))));
+    assert!(matches!(Chunk::from_value(snbt::from_str("{DataVersion:3465, xPos:0, zPos:0}").unwrap()), Err(Error::MissingField(k)) if k == "sections"));
+}
@@ -0,0 +1,58 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashSet},
+    hash::{BuildHasher, RandomState},
+};
+
+use crate::{snbt, Value};
+
+fn parse(s: &str) -> Value {
+    snbt::from_str(s).unwrap()
+}
+
+#[test]
+fn test_value_eq_hash() {
+    let a = parse("{id:\"minecraft:stone\",Count:1b,tag:{Damage:0,Enchantments:[{id:\"a\",lvl:1s}]}}");
+    let b = parse("{tag:{Enchantments:[{lvl:1s,id:\"a\"}],Damage:0},Count:1b,id:\"minecraft:stone\"}");
+    let state = RandomState::new();
+    assert_eq!(a, b);
+    assert_eq!(state.hash_one(&a), state.hash_one(&b));
+    assert_eq!(a.clone(), a);
+
+    assert_ne!(a, parse("{id:\"minecraft:stone\",Count:1b}"));
+    assert_ne!(parse("{a:1}"), parse("{a:1b}"));
+    assert_ne!(parse("[1,2]"), parse("[2,1]"));
+    assert_ne!(parse("[B;1b]"), parse("[1b]"));
+
+    // floats compare by their bits
+    assert_eq!(Value::Double(f64::NAN), Value::Double(f64::NAN));
+    assert_ne!(Value::Float(0.0), Value::Float(-0.0));
+
+    let items: HashSet<Value> = [a.clone(), b, parse("{id:\"minecraft:dirt\",Count:1b}")].into_iter().collect();
+    assert_eq!(items.len(), 2);
+}
+
+#[test]
+fn test_value_ord() {
+    let values: BTreeSet<Value> = ["{b:1,a:2}", "{a:1,b:2}", "1b", "2", "\"x\"", "1.5f", "-0.0d", "0.0d", "[1,2]", "[1]"]
+        .into_iter()
+        .map(parse)
+        .collect();
+    let sorted: Vec<String> = values.iter().map(snbt::to_string).collect();
+    assert_eq!(sorted, ["1b", "2", "1.5f", "-0.0d", "0.0d", "\"x\"", "[1]", "[1,2]", "{a:1,b:2}", "{b:1,a:2}"]);
+    assert_eq!(parse("{b:1,a:2}").cmp(&parse("{a:2,b:1}")), Ordering::Equal);
+}
+
+#[test]
+fn test_value_matches() {
+    let item = parse("{id:\"minecraft:diamond_sword\",Count:1b,tag:{Damage:3,Enchantments:[{id:\"sharpness\",lvl:5s},{id:\"unbreaking\",lvl:3s}]}}");
+    assert!(item.matches(&parse("{}")));
+    assert!(item.matches(&parse("{id:\"minecraft:diamond_sword\"}")));
+    assert!(item.matches(&parse("{tag:{Enchantments:[{id:\"unbreaking\"}]}}")));
+    assert!(item.matches(&parse("{tag:{Enchantments:[{lvl:3s},{id:\"sharpness\"}]}}")));
+    assert!(!item.matches(&parse("{tag:{Enchantments:[{id:\"mending\"}]}}")));
+    assert!(!item.matches(&parse("{tag:{Enchantments:[]}}")));
+    assert!(!item.matches(&parse("{Count:1}")));
+    assert!(!item.matches(&parse("{tag:{Damage:3,Unbreakable:1b}}")));
+    assert!(parse("{a:[]}").matches(&parse("{a:[]}")));
+}
@@ -0,0 +1,65 @@
+use crate::{
+    compound::Entry, reader::{NbtLimits, NbtReader}, snbt, Compound, DuplicateKeys, Error, Nbt, NbtReadTrait, Value,
+};
+
+fn keys(compound: &Compound) -> Vec<&str> {
+    compound.keys().collect()
+}
+
+#[test]
+fn test_compound_order() {
+    let mut compound: Compound = ["c", "a", "b"].iter().map(|k| (k.to_string(), Value::Int(0))).collect();
+    assert_eq!(keys(&compound), ["c", "a", "b"]);
+
+    assert!(compound.insert("a".to_string(), Value::Int(1)).is_some());
+    assert!(compound.insert("d".to_string(), Value::Int(2)).is_none());
+    assert_eq!(keys(&compound), ["c", "a", "b", "d"]);
+    assert_eq!(compound["a"].as_i32(), Some(1));
+
+    assert_eq!(compound.remove("c").and_then(|v| v.as_i32()), Some(0));
+    assert_eq!(keys(&compound), ["a", "b", "d"]);
+    assert_eq!(compound.get_index_of("d"), Some(2));
+    assert_eq!(compound.get("d").and_then(Value::as_i32), Some(2));
+
+    assert!(compound.swap_remove("a").is_some());
+    assert_eq!(keys(&compound), ["d", "b"]);
+    assert_eq!(compound.get_index(0).map(|(k, _)| k), Some("d"));
+    assert!(compound.remove("a").is_none());
+
+    compound.retain(|k, _| k != "d");
+    assert_eq!(keys(&compound), ["b"]);
+    assert_eq!(compound.get_index_of("b"), Some(0));
+}
+
+#[test]
+fn test_compound_entry() {
+    let mut compound = Compound::new();
+    *compound.entry("count".to_string()).or_insert(Value::Int(0)) = Value::Int(1);
+    compound.entry("count".to_string()).and_modify(|v| *v = Value::Int(v.as_i32().unwrap() + 1)).or_insert(Value::Int(0));
+    compound.entry("name".to_string()).or_insert_with(|| Value::String("x".to_string()));
+    assert_eq!(snbt::to_string(&Value::Compound(compound.clone())), "{count:2,name:\"x\"}");
+
+    match compound.entry("name".to_string()) {
+        Entry::Occupied(mut e) => {
+            assert_eq!(e.key(), "name");
+            assert_eq!(e.insert(Value::Byte(1)).as_str(), Some("x"));
+        }
+        Entry::Vacant(_) => panic!(),
+    }
+    assert!(matches!(compound.entry("other".to_string()), Entry::Vacant(e) if e.key() == "other"));
+}
+
+#[test]
+fn test_duplicate_keys() {
+    // {a:1,b:2,a:3}
+    let data = [10, 0, 0, 3, 0, 1, b'a', 0, 0, 0, 1, 3, 0, 1, b'b', 0, 0, 0, 2, 3, 0, 1, b'a', 0, 0, 0, 3, 0];
+    let read = |duplicate_keys| {
+        let limits = NbtLimits { duplicate_keys, ..NbtLimits::default() };
+        Nbt::from_reader(&mut NbtReader::with_limits(&data, limits)).map(|v| snbt::to_string(&v))
+    };
+    assert_eq!(read(DuplicateKeys::LastWins).unwrap(), "{a:3,b:2}");
+    assert_eq!(read(DuplicateKeys::FirstWins).unwrap(), "{a:1,b:2}");
+    assert!(matches!(read(DuplicateKeys::Error), Err(Error::DuplicateKey(key)) if key == "a"));
+
+    assert_eq!(snbt::to_string(&snbt::from_str("{a:1,b:2,a:3}").unwrap()), "{a:3,b:2}");
+}
@@ -0,0 +1,139 @@
+use std::{fs, io::{Read, Write}};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use crate::{
+    compression::{compress, decompress, write_compressed}, lz4::{xxh32, Lz4BlockReader, Lz4BlockWriter}, read_compressed, reader::NbtReader,
+    snbt, write_gzip, write_lz4, write_zlib, writer::{NbtStreamWriter, NbtWriter}, Compression, Error, Nbt,
+    NbtAfter764, NbtReadTrait, NbtWriteTrait,
+};
+
+fn bigtest() -> Vec<u8> {
+    let mut data = Vec::new();
+    GzDecoder::new(&fs::read("src/test/bigtest.nbt").unwrap()[..]).read_to_end(&mut data).unwrap();
+    // `write_to` drops the root name, so compare against the file with an empty one
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &Nbt::from_reader(&mut NbtReader::new(&data)).unwrap()).unwrap();
+    w.data
+}
+
+#[test]
+fn test_detect() {
+    assert_eq!(Compression::detect(&[0x1f, 0x8b, 8]), Compression::Gzip);
+    assert_eq!(Compression::detect(&[0x78, 0x9c]), Compression::Zlib);
+    assert_eq!(Compression::detect(b"LZ4Block\x26"), Compression::Lz4);
+    assert_eq!(Compression::detect(&[10, 0, 0]), Compression::None);
+    assert_eq!(Compression::detect(&[]), Compression::None);
+}
+
+#[test]
+fn test_write_compressed() {
+    let data = bigtest();
+    let value = Nbt::from_reader(&mut NbtReader::new(&data)).unwrap();
+
+    let gzip = write_gzip::<Nbt, _>(Vec::new(), &value).unwrap();
+    let mut decoded = Vec::new();
+    GzDecoder::new(&gzip[..]).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, data);
+
+    let zlib = write_zlib::<Nbt, _>(Vec::new(), &value).unwrap();
+    let mut decoded = Vec::new();
+    ZlibDecoder::new(&zlib[..]).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, data);
+
+    let lz4 = write_lz4::<Nbt, _>(Vec::new(), &value).unwrap();
+    let mut decoded = Vec::new();
+    Lz4BlockReader::new(&lz4[..]).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, data);
+
+    for (bytes, compression) in [
+        (gzip, Compression::Gzip),
+        (zlib, Compression::Zlib),
+        (lz4, Compression::Lz4),
+        (data.clone(), Compression::None),
+    ] {
+        let (read, detected) = read_compressed::<Nbt, _>(&bytes[..]).unwrap();
+        assert_eq!(detected, compression);
+        assert_eq!(snbt::to_string(&read), snbt::to_string(&value));
+        assert_eq!(write_compressed::<Nbt, _>(Vec::new(), &read, Compression::None).unwrap(), data);
+    }
+
+    // the original test file was written by another implementation
+    let (read, detected) = read_compressed::<Nbt, _>(&fs::read("src/test/bigtest.nbt").unwrap()[..]).unwrap();
+    assert_eq!(detected, Compression::Gzip);
+    assert_eq!(snbt::to_string(&read), snbt::to_string(&value));
+}
+
+#[test]
+fn test_compress_bytes() {
+    let data = bigtest();
+    for compression in [Compression::Gzip, Compression::Zlib, Compression::Lz4, Compression::None] {
+        let bytes = compress(&data, compression).unwrap();
+        assert_eq!(Compression::detect(&bytes), compression);
+        assert_eq!(decompress(&bytes, compression).unwrap(), data);
+    }
+    assert!(decompress(&data, Compression::Gzip).is_err());
+}
+
+#[test]
+fn test_stream_writer() {
+    let data = bigtest();
+    let value = Nbt::from_reader(&mut NbtReader::new(&data)).unwrap();
+
+    let path = std::env::temp_dir().join(format!("nbt-stream-writer-{}.nbt", std::process::id()));
+    let mut w = NbtStreamWriter::buffered(fs::File::create(&path).unwrap());
+    Nbt::write_to(&mut w, &value).unwrap();
+    assert_eq!(w.position(), data.len());
+    w.finish().unwrap();
+    assert_eq!(fs::read(&path).unwrap(), data);
+    fs::remove_file(&path).unwrap();
+
+    let mut w = NbtWriter::new();
+    NbtAfter764::write_to(&mut w, &value).unwrap();
+    let mut stream = NbtStreamWriter::new(Vec::new());
+    NbtAfter764::write_to(&mut stream, &value).unwrap();
+    assert_eq!(stream.into_inner(), w.data);
+
+    let mut buf = [0u8; 16];
+    let mut full = NbtStreamWriter::new(&mut buf[..]);
+    assert!(matches!(Nbt::write_to(&mut full, &value), Err(Error::Io(_))));
+    // a buffered sink only fails once its buffer is written out
+    let mut full = NbtStreamWriter::buffered(&mut buf[..]);
+    Nbt::write_to(&mut full, &value).unwrap();
+    assert!(matches!(full.finish(), Err(Error::Io(_))));
+}
+
+#[test]
+fn test_xxh32() {
+    assert_eq!(xxh32(b"", 0), 0x02cc5d05);
+    assert_eq!(xxh32(b"abc", 0), 0x32d153ff);
+    assert_eq!(xxh32(b"Nobody inspects the spammish repetition", 0), 0xe2293b2f);
+}
+
+#[test]
+fn test_lz4_blocks() {
+    // several blocks, one of them too random to compress
+    let mut data: Vec<u8> = (0..200_000u32).map(|i| (i / 7) as u8).collect();
+    let mut x = 1u32;
+    data.extend((0..70_000).map(|_| {
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        x as u8
+    }));
+    let mut w = Lz4BlockWriter::new(Vec::new());
+    w.write_all(&data).unwrap();
+    let encoded = w.finish().unwrap();
+    assert!(encoded.len() < data.len());
+    assert_eq!(&encoded[encoded.len() - 21..encoded.len() - 12], b"LZ4Block\x16");
+
+    let mut decoded = Vec::new();
+    Lz4BlockReader::new(&encoded[..]).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, data);
+
+    let mut corrupt = encoded.clone();
+    corrupt[17] ^= 1;
+    assert!(Lz4BlockReader::new(&corrupt[..]).read_to_end(&mut Vec::new()).is_err());
+    let truncated = &encoded[..encoded.len() - 21];
+    assert!(Lz4BlockReader::new(truncated).read_to_end(&mut Vec::new()).is_err());
+}
@@ -0,0 +1,143 @@
+use nbt_derive::{FromNbt, ToNbt};
+
+use crate::{
+    codec::{self, FromNbt as _, IntArray, ToNbt},
+    reader::NbtReader, snbt, writer::{NbtSink, NbtWriter}, Error, Nbt, NbtReadTrait, NbtWriteTrait,
+};
+
+#[derive(Debug, PartialEq, ToNbt, FromNbt)]
+struct Section {
+    #[nbt(rename = "Y")]
+    y: i8,
+    #[nbt(long_array)]
+    block_states: Vec<i64>,
+    #[nbt(byte_array)]
+    sky_light: Option<Vec<i8>>,
+    #[nbt(default)]
+    palette: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, ToNbt, FromNbt)]
+struct Position {
+    x: i32,
+    z: i32,
+}
+
+#[derive(Debug, PartialEq, ToNbt, FromNbt)]
+struct Chunk {
+    #[nbt(flatten)]
+    position: Position,
+    #[nbt(rename = "Status")]
+    status: String,
+    sections: Vec<Section>,
+    #[nbt(int_array)]
+    heightmap: Vec<i32>,
+    #[nbt(list)]
+    biomes: Vec<i32>,
+    inhabited: Option<i64>,
+    #[nbt(default = "default_version")]
+    version: u32,
+    light_on: bool,
+    scale: f64,
+}
+
+fn default_version() -> u32 {
+    3465
+}
+
+#[derive(Debug, PartialEq, ToNbt, FromNbt)]
+struct Wrapper<T> {
+    value: T,
+}
+
+fn chunk() -> Chunk {
+    Chunk {
+        position: Position { x: -3, z: 7 },
+        status: "minecraft:full".to_string(),
+        sections: vec![
+            Section { y: -4, block_states: vec![1, -1], sky_light: Some(vec![0, 15]), palette: vec!["minecraft:stone".to_string()] },
+            Section { y: -3, block_states: vec![], sky_light: None, palette: vec![] },
+        ],
+        heightmap: vec![64, 65],
+        biomes: vec![1, 2],
+        inhabited: None,
+        version: 3700,
+        light_on: true,
+        scale: 0.5,
+    }
+}
+
+#[test]
+fn test_derive_layout() {
+    let value = Nbt::from_reader(&mut NbtReader::new(&codec::to_vec(&chunk()).unwrap())).unwrap();
+    assert_eq!(
+        snbt::to_string(&value),
+        "{x:-3,z:7,Status:\"minecraft:full\",\
+         sections:[{Y:-4b,block_states:[L;1L,-1L],sky_light:[B;0B,15B],palette:[\"minecraft:stone\"]},\
+         {Y:-3b,block_states:[L;],palette:[]}],\
+         heightmap:[I;64,65],biomes:[1,2],version:3700,light_on:1b,scale:0.5d}"
+    );
+}
+
+#[test]
+fn test_derive_round_trip() {
+    let data = codec::to_vec(&chunk()).unwrap();
+    assert_eq!(codec::from_slice::<Chunk>(&data).unwrap(), chunk());
+
+    // the derived writer produces the same bytes as going through a Value
+    let value = Nbt::from_reader(&mut NbtReader::new(&data)).unwrap();
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &value).unwrap();
+    assert_eq!(w.data, data);
+
+    let wrapped = Wrapper { value: Wrapper { value: IntArray(vec![1, 2, 3]) } };
+    let data = codec::to_vec(&wrapped).unwrap();
+    assert_eq!(codec::from_slice::<Wrapper<Wrapper<IntArray<Vec<i32>>>>>(&data).unwrap(), wrapped);
+}
+
+#[test]
+fn test_derive_missing_and_unknown() {
+    let value = snbt::from_str(
+        "{x:1,z:2,Status:\"empty\",sections:[],heightmap:[I;],biomes:[],light_on:0b,scale:1.0d,extra:{a:[1,2]}}",
+    )
+    .unwrap();
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &value).unwrap();
+    let chunk = codec::from_slice::<Chunk>(&w.data).unwrap();
+    assert_eq!(chunk.version, 3465);
+    assert_eq!(chunk.inhabited, None);
+    assert_eq!(chunk.position, Position { x: 1, z: 2 });
+
+    let value = snbt::from_str("{x:1,Status:\"empty\"}").unwrap();
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &value).unwrap();
+    assert!(matches!(codec::from_slice::<Chunk>(&w.data), Err(Error::MissingField(key)) if key == "z"));
+
+    let value = snbt::from_str("{x:1,z:2L}").unwrap();
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &value).unwrap();
+    assert!(matches!(Position::read_payload(&mut NbtReader::new(&w.data[3..]), 10), Err(Error::TagMismatch { expected: 3, found: 4 })));
+}
+
+// Zero-sized, so a list longer than an i32 can say costs no memory.
+#[derive(Clone, Copy)]
+struct Nothing;
+
+impl ToNbt for Nothing {
+    const TAG: u8 = 1;
+
+    fn write_payload<W: NbtSink>(&self, w: &mut W) -> crate::Result<()> {
+        w.write_i8(0)
+    }
+}
+
+#[test]
+fn test_codec_list_too_long() {
+    let list = [Nothing; i32::MAX as usize + 1];
+    let mut w = NbtWriter::new();
+    match list[..].write_payload(&mut w) {
+        Err(Error::ArrayTooLong { len, max }) => assert_eq!((len, max), (i32::MAX as usize + 1, i32::MAX as usize)),
+        x => panic!("{:?}", x),
+    }
+    assert_eq!(w.data, [1]);
+}
@@ -0,0 +1,92 @@
+use crate::{
+    dialect::{min_payload_size, Numbers, Strings}, reader::NbtReader, snbt, writer::NbtWriter, BedrockNbt, BedrockNetworkNbt, Dialect,
+    Compound, DialectKind, Error, NamedTag, Nbt, NbtAfter764, NbtReadTrait, NbtWriteTrait, Value,
+};
+
+fn encode<D: NbtWriteTrait>(tag: &NamedTag) -> Vec<u8> {
+    let mut w = NbtWriter::new();
+    D::write_named(&mut w, tag).unwrap();
+    w.data
+}
+
+#[test]
+fn test_for_protocol() {
+    assert_eq!(DialectKind::for_protocol(763), DialectKind::Nbt);
+    assert_eq!(DialectKind::for_protocol(764), DialectKind::NbtAfter764);
+    assert_eq!(DialectKind::for_protocol(767), DialectKind::NbtAfter764);
+    assert!(DialectKind::Nbt.named_root());
+    assert!(!DialectKind::NbtAfter764.named_root());
+    assert_eq!(DialectKind::BedrockNetworkNbt.numbers(), Numbers::VarInt);
+    assert_eq!(DialectKind::BedrockNbt.strings(), Strings::Utf8);
+    assert_eq!(DialectKind::NbtAfter764.numbers(), <NbtAfter764 as Dialect>::NUMBERS);
+}
+
+#[test]
+fn test_runtime_dialects() {
+    let mut value = snbt::from_str("{a:300,b:-1L,c:[1.5f],e:[I;1,2],f:{g:[B;3b]}}").unwrap();
+    // NUL is where modified UTF-8 and UTF-8 differ
+    value.as_compound_mut().unwrap().insert("d".to_string(), Value::String("\0é".to_string()));
+    let tag = NamedTag::new("root", value);
+    let expected = [
+        (DialectKind::Nbt, encode::<Nbt>(&tag)),
+        (DialectKind::NbtAfter764, encode::<NbtAfter764>(&tag)),
+        (DialectKind::BedrockNbt, encode::<BedrockNbt>(&tag)),
+        (DialectKind::BedrockNetworkNbt, encode::<BedrockNetworkNbt>(&tag)),
+    ];
+    for (kind, data) in expected {
+        let mut w = NbtWriter::new();
+        kind.write_named(&mut w, &tag).unwrap();
+        assert_eq!(w.data, data, "{:?}", kind);
+
+        let read = kind.read_named(&mut NbtReader::new(&data)).unwrap();
+        assert_eq!(read.value, tag.value);
+        assert_eq!(read.name, if kind.named_root() { "root" } else { "" });
+    }
+
+    // the unnamed root only drops the name; the payload is identical
+    let named = encode::<Nbt>(&tag);
+    let unnamed = encode::<NbtAfter764>(&tag);
+    assert_eq!(unnamed[0], 10);
+    assert_eq!(unnamed[1..], named[3 + "root".len()..]);
+}
+
+fn check_min_payload_sizes<D: Dialect + NbtWriteTrait>() {
+    let smallest = [
+        Value::Byte(0),
+        Value::Short(0),
+        Value::Int(0),
+        Value::Long(0),
+        Value::Float(0.0),
+        Value::Double(0.0),
+        Value::ByteArray(Vec::new()),
+        Value::String(String::new()),
+        Value::List(Vec::new()),
+        Value::Compound(Compound::new()),
+        Value::IntArray(Vec::new()),
+        Value::LongArray(Vec::new()),
+    ];
+    let list = |values: Vec<Value>| {
+        let mut w = NbtWriter::new();
+        D::write_to(&mut w, &Value::Compound([("l".to_string(), Value::List(values))].into_iter().collect())).unwrap();
+        w.data.len()
+    };
+    for value in smallest {
+        let tag = value.tag();
+        assert_eq!(list(vec![value]) - list(Vec::new()), min_payload_size::<D>(tag), "tag {}", tag);
+    }
+    assert_eq!(min_payload_size::<D>(0), 0);
+    assert_eq!(min_payload_size::<D>(13), 0);
+}
+
+#[test]
+fn test_min_payload_sizes() {
+    check_min_payload_sizes::<Nbt>();
+    check_min_payload_sizes::<BedrockNbt>();
+    check_min_payload_sizes::<BedrockNetworkNbt>();
+
+    // a list of End or unknown elements is refused before anything is reserved for it
+    let data = [10, 0, 0, 9, 0, 1, b'l', 0, 0x7f, 0xff, 0xff, 0xff, 0];
+    assert!(matches!(Nbt::from_reader(&mut NbtReader::new(&data)), Err(Error::InvalidTag(0))));
+    let data = [10, 0, 0, 9, 0, 1, b'l', 13, 0, 0, 0, 0, 0];
+    Nbt::from_reader(&mut NbtReader::new(&data)).unwrap();
+}
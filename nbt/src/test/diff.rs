@@ -0,0 +1,51 @@
+use crate::{diff, merge, snbt, Error, Patch, Value};
+
+fn parse(s: &str) -> Value {
+    snbt::from_str(s).unwrap()
+}
+
+#[test]
+fn test_diff() {
+    let old = parse("{Data:{Time:100L,GameRules:{doDaylightCycle:\"true\"},Player:{Pos:[0.0d,64.0d,0.0d],Inventory:[{Slot:0b}]}},\"odd key\":1}");
+    let new = parse("{Data:{Time:250L,GameRules:{doDaylightCycle:\"true\",keepInventory:\"true\"},Player:{Pos:[0.0d,70.5d,0.0d],Inventory:[{Slot:0b},{Slot:1b}]}}}");
+    let patch = diff(&old, &new);
+    assert_eq!(
+        patch.to_string(),
+        "~ Data.Time: 100L -> 250L\n\
+         + Data.GameRules.keepInventory: \"true\"\n\
+         ~ Data.Player.Pos[1]: 64.0d -> 70.5d\n\
+         ~ Data.Player.Inventory: [{Slot:0b}] -> [{Slot:0b},{Slot:1b}]\n\
+         - \"odd key\": 1\n"
+    );
+    assert!(diff(&old, &old).is_empty());
+
+    let mut value = old.clone();
+    patch.apply(&mut value).unwrap();
+    assert_eq!(value, new);
+    assert!(matches!(patch.apply(&mut value), Err(Error::PatchConflict { path, .. }) if path == "Data.Time"));
+    patch.reverse().apply(&mut value).unwrap();
+    assert_eq!(value, old);
+
+    // the encoded patch survives a trip through SNBT
+    let encoded = snbt::to_string(&patch.to_value());
+    let decoded = Patch::from_value(&snbt::from_str(&encoded).unwrap()).unwrap();
+    assert_eq!(decoded, patch);
+
+    let root = diff(&Value::Int(1), &parse("{a:1}"));
+    assert_eq!(root.to_string(), "~ : 1 -> {a:1}\n");
+    let mut value = Value::Int(1);
+    Patch::from_value(&root.to_value()).unwrap().apply(&mut value).unwrap();
+    assert_eq!(value, parse("{a:1}"));
+}
+
+#[test]
+fn test_merge() {
+    let mut target = parse("{a:{b:1,c:[1,2]},d:\"x\",e:{f:1}}");
+    merge(&mut target, &parse("{a:{c:[3],g:2b},d:{h:1},e:5}"));
+    assert_eq!(target, parse("{a:{b:1,c:[3],g:2b},d:{h:1},e:5}"));
+    assert_eq!(snbt::to_string(&target), "{a:{b:1,c:[3],g:2b},d:{h:1},e:5}");
+
+    let mut target = Value::Int(1);
+    merge(&mut target, &parse("{a:1}"));
+    assert_eq!(target, parse("{a:1}"));
+}
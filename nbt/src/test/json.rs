@@ -0,0 +1,44 @@
+use std::{fs, io::Read};
+
+use flate2::read::GzDecoder;
+use serde_json::json;
+
+use crate::{json, reader::NbtReader, snbt, writer::NbtWriter, Error, Nbt, NbtReadTrait, NbtWriteTrait, Value};
+
+#[test]
+fn test_typed_json_round_trip() {
+    let mut data = Vec::new();
+    GzDecoder::new(&fs::read("src/test/bigtest.nbt").unwrap()[..]).read_to_end(&mut data).unwrap();
+    let tag = Nbt::from_reader_named(&mut NbtReader::new(&data)).unwrap();
+
+    let text = serde_json::to_string(&json::to_typed(&tag.value)).unwrap();
+    let value = json::from_typed(&serde_json::from_str(&text).unwrap()).unwrap();
+    let mut w = NbtWriter::new();
+    Nbt::write_to_with_name(&mut w, &tag.name, &value).unwrap();
+    assert_eq!(w.data, data);
+
+    let extremes = Value::List(vec![Value::Float(f32::NAN), Value::Float(-0.0), Value::Float(f32::INFINITY), Value::Float(0.1)]);
+    let back = json::from_typed(&json::to_typed(&extremes)).unwrap();
+    assert_eq!(back, extremes);
+    let value = snbt::from_str("{l:9223372036854775807L,d:-1.0E300d,b:-128b}").unwrap();
+    assert_eq!(json::from_typed(&json::to_typed(&value)).unwrap(), value);
+
+    assert_eq!(json::to_typed(&snbt::from_str("{a:[I;1]}").unwrap()), json!({"type":"compound","value":{"a":{"type":"int_array","value":[1]}}}));
+    assert!(matches!(json::from_typed(&json!({"type":"byte","value":300})), Err(Error::Json(_))));
+    assert!(matches!(json::from_typed(&json!({"type":"list","value":[{"type":"byte","value":1},{"type":"int","value":1}]})), Err(Error::ListTypeNotSame)));
+}
+
+#[test]
+fn test_plain_json() {
+    let mut value = snbt::from_str("{z:1b,a:[L;5L,6L],s:\"hi\",n:{f:0.5f},l:[{x:1s}]}").unwrap();
+    value.get_mut("n").unwrap().as_compound_mut().unwrap().insert("nan".to_string(), Value::Double(f64::NAN));
+    assert_eq!(
+        serde_json::to_string(&json::to_plain(&value)).unwrap(),
+        r#"{"z":1,"a":[5,6],"s":"hi","n":{"f":0.5,"nan":null},"l":[{"x":1}]}"#
+    );
+
+    let parsed = json::from_plain(&json!({"a": 1, "b": 3_000_000_000i64, "c": 1.5, "d": true, "e": [{"x": "y"}], "f": []})).unwrap();
+    assert_eq!(snbt::to_string(&parsed), "{a:1,b:3000000000L,c:1.5d,d:1b,e:[{x:\"y\"}],f:[]}");
+    assert!(json::from_plain(&json!([1, "a"])).is_err());
+    assert!(json::from_plain(&json!({"a": null})).is_err());
+}
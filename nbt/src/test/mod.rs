@@ -1,17 +1,35 @@
+mod bedrock;
+mod chunk;
+mod cmp;
+mod compound;
+mod compression;
+mod derive;
+mod dialect;
+mod diff;
+mod json;
+mod path;
+mod prop;
+mod region;
+mod ser;
+mod snbt;
+mod stream;
+mod tree;
+mod value_ref;
+
 use flate2::read::GzDecoder;
 
-use crate::{reader::NbtReader, writer::NbtWriter, Nbt, NbtReadTrait, NbtWriteTrait};
+use crate::{mutf8, reader::{NbtLimits, NbtReader}, writer::NbtWriter, Error, NamedTag, Nbt, NbtAfter764, NbtReadTrait, NbtWriteTrait, Value};
 use std::{fs, io::Read};
 
 #[test]
 fn test_nbt() {
-    let mut data = fs::read("src/test/hello_world.nbt").unwrap();
+    let data = fs::read("src/test/hello_world.nbt").unwrap();
     println!("{:?}", data);
-    let mut reader = NbtReader::new(&mut data);
-    let value = Nbt::from_reader(&mut reader).unwrap();
+    let mut reader = NbtReader::new(&data);
+    let value = Nbt::from_reader_named(&mut reader).unwrap();
     println!("{:?}", value);
     let mut writer = NbtWriter::new();
-    Nbt::write_to(&mut writer, &value).unwrap();
+    Nbt::write_named(&mut writer, &value).unwrap();
     println!("{:?}", writer.data);
     assert_eq!(data, writer.data);
 }
@@ -22,11 +40,180 @@ fn test_nbt_with_gzip() {
     let mut d = GzDecoder::new(&data1[..]);
     let mut data = Vec::new();
     d.read_to_end(&mut data).unwrap();
-    let mut reader = NbtReader::new(&mut data);
-    let value = Nbt::from_reader(&mut reader).unwrap();
+    let mut reader = NbtReader::new(&data);
+    let value = Nbt::from_reader_named(&mut reader).unwrap();
     println!("{:?}", value);
     let mut writer = NbtWriter::new();
-    Nbt::write_to(&mut writer, &value).unwrap();
+    Nbt::write_named(&mut writer, &value).unwrap();
     println!("{:?}", writer.data);
     assert_eq!(data, writer.data);
-}
\ No newline at end of file
+}
+#[test]
+fn test_truncated_input() {
+    let data1 = fs::read("src/test/bigtest.nbt").unwrap();
+    let mut d = GzDecoder::new(&data1[..]);
+    let mut data = Vec::new();
+    d.read_to_end(&mut data).unwrap();
+    for len in 0..data.len() {
+        let prefix = data[..len].to_vec();
+        let mut reader = NbtReader::new(&prefix);
+        match Nbt::from_reader(&mut reader) {
+            Err(Error::UnexpectedEof { offset, needed }) => assert!(offset + needed > len),
+            x => panic!("prefix of {} bytes: expected UnexpectedEof, got {:?}", len, x),
+        }
+    }
+
+    // the same document without its root name
+    let name_len = u16::from_be_bytes([data[1], data[2]]) as usize;
+    let mut unnamed = vec![10];
+    unnamed.extend_from_slice(&data[3 + name_len..]);
+    for len in 0..unnamed.len() {
+        let prefix = unnamed[..len].to_vec();
+        let mut reader = NbtReader::new(&prefix);
+        match NbtAfter764::from_reader(&mut reader) {
+            Err(Error::UnexpectedEof { offset, needed }) => assert!(offset + needed > len),
+            x => panic!("prefix of {} bytes: expected UnexpectedEof, got {:?}", len, x),
+        }
+    }
+}
+
+#[test]
+fn test_truncated_array() {
+    let data = vec![10, 0, 0, 12, 0, 1, b'a', 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 1];
+    let mut reader = NbtReader::new(&data);
+    match Nbt::from_reader(&mut reader) {
+        Err(Error::UnexpectedEof { offset: 11, needed: 16 }) => {}
+        x => panic!("expected UnexpectedEof, got {:?}", x),
+    }
+}
+
+#[test]
+fn test_reader_cursor() {
+    let data = [1, 2, 3];
+    let mut reader = NbtReader::new(&data);
+    reader.roll_down(2).unwrap();
+    assert!(matches!(reader.roll_down(2), Err(Error::UnexpectedEof { offset: 2, needed: 2 })));
+    assert!(matches!(reader.roll_back(3), Err(Error::UnexpectedEof { offset: 2, needed: 3 })));
+    assert_eq!(reader.remaining(), 1);
+    reader.roll_back(2).unwrap();
+    assert_eq!(reader.remaining(), 3);
+}
+
+#[test]
+fn test_depth_limit() {
+    let mut data = vec![10, 0, 0, 9, 0, 0];
+    for _ in 0..NbtLimits::MAX_DEPTH {
+        data.extend_from_slice(&[9, 0, 0, 0, 1]);
+    }
+    data.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    let mut reader = NbtReader::new(&data);
+    match Nbt::from_reader(&mut reader) {
+        Err(Error::DepthLimitExceeded(NbtLimits::MAX_DEPTH)) => {}
+        x => panic!("expected DepthLimitExceeded, got {:?}", x),
+    }
+}
+
+#[test]
+fn test_size_limit() {
+    // an 11 byte document declaring a list of i32::MAX compounds
+    let data = vec![10, 0, 0, 9, 0, 0, 10, 0x7f, 0xff, 0xff, 0xff];
+    let mut reader = NbtReader::with_limits(&data, NbtLimits::network());
+    match Nbt::from_reader(&mut reader) {
+        Err(Error::SizeLimitExceeded { max: NbtLimits::NETWORK_MAX_BYTES, .. }) => {}
+        x => panic!("expected SizeLimitExceeded, got {:?}", x),
+    }
+
+    let data = fs::read("src/test/hello_world.nbt").unwrap();
+    let limits = NbtLimits { max_bytes: 64, ..NbtLimits::default() };
+    let mut reader = NbtReader::with_limits(&data, limits);
+    assert!(matches!(Nbt::from_reader(&mut reader), Err(Error::SizeLimitExceeded { .. })));
+    let mut reader = NbtReader::with_limits(&data, NbtLimits::network());
+    Nbt::from_reader(&mut reader).unwrap();
+    assert_eq!(reader.accounted(), 48 + 2 * 11 + 36 + 36 + 36 + 2 * 4 + 2 * 9);
+}
+
+#[test]
+fn test_array_limits() {
+    let data = vec![10, 0, 0, 11, 0, 0, 0xff, 0xff, 0xff, 0xfe];
+    let mut reader = NbtReader::new(&data);
+    assert!(matches!(Nbt::from_reader(&mut reader), Err(Error::NegativeLength(-2))));
+
+    let data = vec![10, 0, 0, 7, 0, 0, 0, 0, 1, 0];
+    let limits = NbtLimits { max_array_len: 16, ..NbtLimits::default() };
+    let mut reader = NbtReader::with_limits(&data, limits);
+    assert!(matches!(Nbt::from_reader(&mut reader), Err(Error::ArrayTooLong { len: 256, max: 16 })));
+}
+
+#[test]
+fn test_mutf8_java_roundtrip() {
+    // written by java.io.DataOutputStream.writeUTF
+    let data1 = fs::read("src/test/mutf8.nbt").unwrap();
+    let mut d = GzDecoder::new(&data1[..]);
+    let mut data = Vec::new();
+    d.read_to_end(&mut data).unwrap();
+    let mut reader = NbtReader::new(&data);
+    let NamedTag { name, value } = Nbt::from_reader_named(&mut reader).unwrap();
+    let Value::Compound(entries) = &value else { panic!("{:?}", value) };
+    assert_eq!(name, "mutf8 test");
+    let expected = [
+        ("nul", "a\0b".to_string()),
+        ("emoji", "😀 smile 🌍".to_string()),
+        ("cjk", "你好世界".to_string()),
+        ("latin", "café üß".to_string()),
+        ("long", "x".repeat(40000)),
+        ("é\0key", String::new()),
+    ];
+    assert_eq!(entries.len(), expected.len());
+    for ((key, value), (expected_key, expected_value)) in entries.iter().zip(expected) {
+        assert_eq!(key, expected_key);
+        match value {
+            Value::String(v) => assert_eq!(*v, expected_value),
+            x => panic!("{:?}", x),
+        }
+    }
+    let mut writer = NbtWriter::new();
+    Nbt::write_to_with_name(&mut writer, &name, &value).unwrap();
+    assert_eq!(data, writer.data);
+}
+
+#[test]
+fn test_mutf8_encoding() {
+    assert_eq!(&*mutf8::encode("\0"), &[0xc0, 0x80]);
+    assert_eq!(&*mutf8::encode("😀"), &[0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+    assert_eq!(mutf8::encoded_len("a\0😀é"), 1 + 2 + 6 + 2);
+    assert_eq!(mutf8::decode(&[0xc0, 0x80]).unwrap(), "\0");
+    assert!(matches!(mutf8::decode(&[0x61, 0xe4, 0xbd]), Err(Error::InvalidMutf8(3))));
+    assert!(matches!(mutf8::decode(&[0xed, 0xa0, 0xbd]), Err(Error::InvalidMutf8(_))));
+}
+
+#[test]
+fn test_string_too_long() {
+    let mut writer = NbtWriter::new();
+    assert!(matches!(Nbt::write_nbt_string(&mut writer, &"x".repeat(65536)), Err(Error::StringTooLong { len: 65536, max: 65535 })));
+    assert!(matches!(Nbt::write_nbt_string(&mut writer, &"\0".repeat(40000)), Err(Error::StringTooLong { len: 80000, max: 65535 })));
+    Nbt::write_nbt_string(&mut writer, &"x".repeat(65535)).unwrap();
+    assert_eq!(&writer.data[..2], &[0xff, 0xff]);
+}
+
+#[test]
+fn test_root_names() {
+    // {a:{b:1b}} built by hand, with no names anywhere but the keys
+    let inner = [("b".to_string(), Value::Byte(1))].into_iter().collect();
+    let value = Value::Compound([("a".to_string(), Value::Compound(inner))].into_iter().collect());
+    let body = [10, 0, 1, b'a', 1, 0, 1, b'b', 1, 0, 0];
+
+    let mut writer = NbtWriter::new();
+    Nbt::write_to_with_name(&mut writer, "root", &value).unwrap();
+    assert_eq!(writer.data, [&[10, 0, 4][..], b"root", &body].concat());
+    let tag = Nbt::from_reader_named(&mut NbtReader::new(&writer.data)).unwrap();
+    assert_eq!(tag.name, "root");
+
+    let mut writer = NbtWriter::new();
+    Nbt::write_to(&mut writer, &tag.value).unwrap();
+    assert_eq!(writer.data, [&[10, 0, 0][..], &body].concat());
+
+    let mut writer = NbtWriter::new();
+    NbtAfter764::write_named(&mut writer, &tag).unwrap();
+    assert_eq!(writer.data, [&[10][..], &body].concat());
+    assert_eq!(NbtAfter764::from_reader_named(&mut NbtReader::new(&writer.data)).unwrap().name, "");
+}
@@ -0,0 +1,137 @@
+use std::{fs, io::Read};
+
+use flate2::read::GzDecoder;
+
+use crate::{path::matches, reader::NbtReader, snbt, writer::NbtWriter, Error, Nbt, NbtPath, NbtReadTrait, NbtWriteTrait, Value};
+
+fn bigtest() -> Value {
+    let mut data = Vec::new();
+    GzDecoder::new(&fs::read("src/test/bigtest.nbt").unwrap()[..]).read_to_end(&mut data).unwrap();
+    Nbt::from_reader(&mut NbtReader::new(&data)).unwrap()
+}
+
+fn query(value: &Value, path: &str) -> Vec<String> {
+    value.query(path).unwrap().into_iter().map(snbt::to_string).collect()
+}
+
+#[test]
+fn test_accessors() {
+    let mut value = bigtest();
+    assert_eq!(value.get("intTest").and_then(Value::as_i32), Some(2147483647));
+    assert_eq!(value.get("intTest").and_then(Value::as_i64), None);
+    assert_eq!(value.get("stringTest").and_then(Value::as_str).map(str::len), Some(41));
+    assert_eq!(value.get("listTest (long)").and_then(Value::as_list).map(<[_]>::len), Some(5));
+    assert_eq!(value.get("byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))").and_then(Value::as_byte_array).map(<[_]>::len), Some(1000));
+    let egg = value.get("nested compound test").and_then(|v| v.get("egg")).unwrap();
+    assert_eq!(egg.get("value").and_then(Value::as_f32), Some(0.5));
+    assert!(value.get("missing").is_none());
+    assert!(egg.get("name").unwrap().get("name").is_none());
+
+    *value.get_mut("shortTest").unwrap() = Value::Short(1);
+    assert_eq!(value.get("shortTest").and_then(Value::as_i16), Some(1));
+    value.as_compound_mut().unwrap().retain(|k, _| k == "shortTest");
+    assert_eq!(value.as_compound().unwrap().len(), 1);
+}
+
+#[test]
+fn test_path_parse() {
+    for path in [
+        "foo",
+        "foo.bar[0]",
+        "foo[-1].bar",
+        "{a:1b}.foo",
+        "Inventory[{id:\"minecraft:stone\"}].Count",
+        "foo{a:[1,2]}.bar",
+        "a[][]",
+        "\"a b\".'c\"d'",
+    ] {
+        assert_eq!(NbtPath::parse(path).unwrap().to_string(), path);
+    }
+    assert_eq!("'a'.b".parse::<NbtPath>().unwrap().to_string(), "a.b");
+
+    for (path, offset) in [("", 0), ("a.", 2), ("a.{b:1}", 2), ("a[x]", 2), ("a[0", 3), ("a..b", 2), ("a{b:}", 4), ("a]", 1)] {
+        match NbtPath::parse(path) {
+            Err(Error::InvalidPath { offset: o, .. }) => assert_eq!(o, offset, "{}", path),
+            x => panic!("{}: expected an error, got {:?}", path, x),
+        }
+    }
+}
+
+#[test]
+fn test_path_get() {
+    let value = bigtest();
+    assert_eq!(query(&value, "\"nested compound test\".egg.name"), ["\"Eggbert\""]);
+    assert_eq!(query(&value, "'nested compound test'.ham{name:\"Hampus\"}.value"), ["0.75f"]);
+    assert!(query(&value, "'nested compound test'.ham{name:\"Eggbert\"}.value").is_empty());
+    assert_eq!(query(&value, "\"listTest (long)\"[1]"), ["12L"]);
+    assert_eq!(query(&value, "\"listTest (long)\"[-1]"), ["15L"]);
+    assert!(query(&value, "\"listTest (long)\"[5]").is_empty());
+    assert_eq!(query(&value, "\"listTest (long)\"[]").len(), 5);
+    assert_eq!(query(&value, "\"listTest (compound)\"[].name"), ["\"Compound tag #0\"", "\"Compound tag #1\""]);
+    assert_eq!(query(&value, "\"listTest (compound)\"[{name:\"Compound tag #1\"}].name"), ["\"Compound tag #1\""]);
+    assert_eq!(query(&value, "{byteTest:127b}.shortTest"), ["32767s"]);
+    assert!(query(&value, "{byteTest:1b}.shortTest").is_empty());
+    assert!(query(&value, "intTest.foo").is_empty());
+
+    let mut value = value;
+    for v in NbtPath::parse("\"listTest (long)\"[]").unwrap().get_mut(&mut value) {
+        *v = Value::Long(0);
+    }
+    assert_eq!(query(&value, "\"listTest (long)\""), ["[0L,0L,0L,0L,0L]"]);
+}
+
+#[test]
+fn test_path_set_and_remove() {
+    let mut value = snbt::from_str("{list:[{id:\"a\",n:1},{id:\"b\",n:2}],ints:[I;1,2,3]}").unwrap();
+    let set = |value: &mut Value, path: &str, v: &str| NbtPath::parse(path).unwrap().set(value, snbt::from_str(v).unwrap());
+    let remove = |value: &mut Value, path: &str| NbtPath::parse(path).unwrap().remove(value);
+
+    assert_eq!(set(&mut value, "list[{id:\"b\"}].n", "5"), 1);
+    assert_eq!(set(&mut value, "list[].flag", "1b"), 2);
+    assert_eq!(set(&mut value, "ints[-1]", "7"), 1);
+    assert_eq!(set(&mut value, "ints[0]", "7L"), 0);
+    assert_eq!(set(&mut value, "list[0]", "1"), 0);
+    assert_eq!(
+        snbt::to_string(&value),
+        "{list:[{id:\"a\",n:1,flag:1b},{id:\"b\",n:5,flag:1b}],ints:[I;1,2,7]}"
+    );
+
+    // missing compounds and lists are created on the way
+    assert_eq!(set(&mut value, "a.b[].c", "{d:1}"), 1);
+    assert_eq!(set(&mut value, "list[{id:\"c\"}].n", "3"), 1);
+    assert_eq!(set(&mut value, "x{y:1}.z", "2"), 1);
+    assert_eq!(
+        snbt::to_string(&value),
+        "{list:[{id:\"a\",n:1,flag:1b},{id:\"b\",n:5,flag:1b},{id:\"c\",n:3}],ints:[I;1,2,7],a:{b:[{c:{d:1}}]},x:{y:1,z:2}}"
+    );
+    // compounds created along the way are written under their key
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &value).unwrap();
+    assert_eq!(snbt::to_string(&Nbt::from_reader(&mut NbtReader::new(&w.data)).unwrap()), snbt::to_string(&value));
+
+    assert_eq!(remove(&mut value, "list[{flag:1b}]"), 2);
+    assert_eq!(remove(&mut value, "ints[1]"), 1);
+    assert_eq!(remove(&mut value, "a.b[]"), 1);
+    assert_eq!(remove(&mut value, "x{y:2}"), 0);
+    assert_eq!(remove(&mut value, "x{y:1}"), 1);
+    assert_eq!(remove(&mut value, "nothing.here"), 0);
+    assert_eq!(snbt::to_string(&value), "{list:[{id:\"c\",n:3}],ints:[I;1,7],a:{b:[]}}");
+}
+
+#[test]
+fn test_matches() {
+    let value = snbt::from_str("{a:1,b:[1,2,3],c:{d:\"x\",e:[]}}").unwrap();
+    for (pattern, expected) in [
+        ("{}", true),
+        ("{a:1}", true),
+        ("{a:1b}", false),
+        ("{b:[3,1]}", true),
+        ("{b:[4]}", false),
+        ("{b:[]}", false),
+        ("{c:{e:[]}}", true),
+        ("{c:{d:\"y\"}}", false),
+        ("{f:1}", false),
+    ] {
+        assert_eq!(matches(&snbt::from_str(pattern).unwrap(), &value), expected, "{}", pattern);
+    }
+}
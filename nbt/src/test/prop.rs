@@ -0,0 +1,123 @@
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::{
+    compression::write_compressed, read_compressed, reader::NbtReader, writer::NbtWriter, Compound, Compression,
+    DialectKind, Error, NamedTag, Nbt, NbtWriteTrait, Value,
+};
+
+// Mostly short, with the lengths where encodings change size or chunk sections live.
+fn array_len() -> impl Strategy<Value = usize> {
+    prop_oneof![Just(0), Just(1), 2..40usize, Just(255), Just(256), Just(4096)]
+}
+
+// Includes NUL and characters outside the BMP, which modified UTF-8 encodes differently.
+fn string() -> impl Strategy<Value = String> {
+    prop_oneof!["[a-z]{0,8}", any::<String>(), "[\0é\u{10000}-\u{10ffff}]{0,4}"]
+}
+
+fn leaf() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        any::<i8>().prop_map(Value::Byte),
+        any::<i16>().prop_map(Value::Short),
+        any::<i32>().prop_map(Value::Int),
+        any::<i64>().prop_map(Value::Long),
+        any::<f32>().prop_map(Value::Float),
+        any::<f64>().prop_map(Value::Double),
+        array_len().prop_flat_map(|len| vec(any::<i8>(), len)).prop_map(Value::ByteArray),
+        string().prop_map(Value::String),
+        array_len().prop_flat_map(|len| vec(any::<i32>(), len)).prop_map(Value::IntArray),
+        array_len().prop_flat_map(|len| vec(any::<i64>(), len)).prop_map(Value::LongArray),
+    ]
+}
+
+fn compound(value: impl Strategy<Value = Value>) -> impl Strategy<Value = Value> {
+    vec((string(), value), 0..6).prop_map(|entries| Value::Compound(entries.into_iter().collect::<Compound>()))
+}
+
+/// Any tree, including empty lists, lists of lists and compounds inside lists.
+fn value() -> impl Strategy<Value = Value> {
+    leaf().prop_recursive(4, 48, 6, |inner| {
+        prop_oneof![
+            // lists keep the elements with the tag of the first, so they may also end up empty
+            vec(inner.clone(), 0..6).prop_map(|values| {
+                let tag = values.first().map(Value::tag);
+                Value::List(values.into_iter().filter(|v| Some(v.tag()) == tag).collect())
+            }),
+            compound(inner),
+        ]
+    })
+}
+
+fn root() -> impl Strategy<Value = NamedTag> {
+    (string(), compound(value())).prop_map(|(name, value)| NamedTag::new(name, value))
+}
+
+const DIALECTS: [DialectKind; 4] =
+    [DialectKind::Nbt, DialectKind::NbtAfter764, DialectKind::BedrockNbt, DialectKind::BedrockNetworkNbt];
+
+proptest! {
+    #[test]
+    fn test_round_trip(tag in root()) {
+        for kind in DIALECTS {
+            let mut w = NbtWriter::new();
+            kind.write_named(&mut w, &tag).unwrap();
+            let mut r = NbtReader::new(&w.data);
+            let read = kind.read_named(&mut r).unwrap();
+            prop_assert_eq!(r.remaining(), 0);
+            prop_assert_eq!(&read.value, &tag.value, "{:?}", kind);
+            prop_assert_eq!(&read.name, if kind.named_root() { &tag.name } else { "" });
+        }
+    }
+
+    #[test]
+    fn test_round_trip_compressed(tag in root()) {
+        for compression in [Compression::Gzip, Compression::Zlib, Compression::Lz4, Compression::None] {
+            let data = write_compressed::<Nbt, _>(Vec::new(), &tag.value, compression).unwrap();
+            let (read, detected) = read_compressed::<Nbt, _>(&data[..]).unwrap();
+            prop_assert_eq!(detected, compression);
+            prop_assert_eq!(&read, &tag.value);
+        }
+    }
+
+    #[test]
+    fn test_list_of_one_type(values in vec(leaf(), 2..6)) {
+        let mixed = values.iter().any(|v| v.tag() != values[0].tag());
+        let value = Value::Compound([("list".to_string(), Value::List(values))].into_iter().collect());
+        for kind in DIALECTS {
+            let written = kind.write(&mut NbtWriter::new(), &value);
+            prop_assert_eq!(matches!(written, Err(Error::ListTypeNotSame)), mixed);
+        }
+    }
+}
+
+#[test]
+fn test_string_len_limit() {
+    let value = |len: usize| Value::Compound([("s".to_string(), Value::String("x".repeat(len)))].into_iter().collect());
+    for kind in DIALECTS {
+        // Bedrock's network VarInt lengths are capped at i16::MAX, the fixed 16-bit ones at u16::MAX
+        let max = if kind == DialectKind::BedrockNetworkNbt { i16::MAX as usize } else { u16::MAX as usize };
+        let mut w = NbtWriter::new();
+        kind.write(&mut w, &value(max)).unwrap();
+        assert_eq!(kind.read(&mut NbtReader::new(&w.data)).unwrap(), value(max));
+        for len in [max + 1, 100_000] {
+            match kind.write(&mut NbtWriter::new(), &value(len)) {
+                Err(Error::StringTooLong { len: l, max: m }) => assert_eq!((l, m), (len, max)),
+                x => panic!("{:?}: {:?}", kind, x),
+            }
+        }
+    }
+    // a network payload declaring a longer string is refused before it is read
+    let mut data = vec![10, 0, 8, 1, b's'];
+    data.extend_from_slice(&[0xa0, 0x8d, 0x06]);
+    data.extend(std::iter::repeat_n(b'x', 100_000));
+    data.push(0);
+    match DialectKind::BedrockNetworkNbt.read(&mut NbtReader::new(&data)) {
+        Err(Error::StringTooLong { len: 100_000, max: 32767 }) => {}
+        x => panic!("{:?}", x),
+    }
+    // the limit applies to the encoded length, which grows for NUL in modified UTF-8
+    let nul = Value::Compound([("s".to_string(), Value::String("\0".repeat(40000)))].into_iter().collect());
+    assert!(matches!(Nbt::write_to(&mut NbtWriter::new(), &nul), Err(Error::StringTooLong { len: 80000, .. })));
+    DialectKind::BedrockNbt.write(&mut NbtWriter::new(), &nul).unwrap();
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{region::{Region, SECTOR_SIZE}, snbt, Compression, Error, Value};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nbt-region-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn chunk(x: usize, z: usize, padding: usize) -> Value {
+    snbt::from_str(&format!("{{xPos:{}, zPos:{}, Status:\"minecraft:full\", Padding:[B;{}]}}", x, z, vec!["1b"; padding].join(","))).unwrap()
+}
+
+#[test]
+fn test_region_round_trip() {
+    let dir = temp_dir("round-trip");
+    let path = dir.join("r.-1.2.mca");
+    let compressions = [Compression::Gzip, Compression::Zlib, Compression::None, Compression::Lz4];
+    {
+        let mut region = Region::open(&path).unwrap();
+        assert_eq!(region.position(), Some((-1, 2)));
+        assert_eq!(region.chunks().count(), 0);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 2 * SECTOR_SIZE as u64);
+        for (i, compression) in compressions.into_iter().enumerate() {
+            region.write_chunk_with(i, 31 - i, &chunk(i, 31 - i, 100), compression).unwrap();
+        }
+        assert!(region.read_chunk(5, 5).unwrap().is_none());
+    }
+
+    let mut region = Region::open(&path).unwrap();
+    assert_eq!(region.chunks().collect::<Vec<_>>(), vec![(3, 28), (2, 29), (1, 30), (0, 31)]);
+    for (i, compression) in compressions.into_iter().enumerate() {
+        assert!(region.contains(i, 31 - i).unwrap());
+        assert!(region.timestamp(i, 31 - i).unwrap() > 0);
+        assert_eq!(region.read_raw(i, 31 - i).unwrap().unwrap().0, compression);
+        let value = region.read_chunk(i, 31 - i).unwrap().unwrap();
+        assert_eq!(snbt::to_string(&value), snbt::to_string(&chunk(i, 31 - i, 100)));
+    }
+    assert_eq!(fs::metadata(&path).unwrap().len() % SECTOR_SIZE as u64, 0);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_region_sectors() {
+    let dir = temp_dir("sectors");
+    let mut region = Region::open(dir.join("r.0.0.mca")).unwrap();
+
+    // uncompressed so that the padding decides the sector counts
+    region.write_chunk_with(0, 0, &chunk(0, 0, 3 * SECTOR_SIZE), Compression::None).unwrap();
+    region.write_chunk_with(1, 0, &chunk(1, 0, 10), Compression::None).unwrap();
+    assert_eq!(region.sectors(), 2 + 4 + 1);
+
+    // the freed sectors are reused by the next chunk that fits
+    region.write_chunk_with(0, 0, &chunk(0, 0, 10), Compression::None).unwrap();
+    region.write_chunk_with(2, 0, &chunk(2, 0, 2 * SECTOR_SIZE), Compression::None).unwrap();
+    assert_eq!(region.sectors(), 2 + 4 + 1 + 1);
+
+    region.remove_chunk(2, 0).unwrap();
+    assert!(!region.contains(2, 0).unwrap());
+    let timestamp = region.timestamp(1, 0).unwrap();
+    region.defragment().unwrap();
+    assert_eq!(region.sectors(), 2 + 1 + 1);
+    assert_eq!(fs::metadata(dir.join("r.0.0.mca")).unwrap().len(), 4 * SECTOR_SIZE as u64);
+    assert_eq!(region.timestamp(1, 0).unwrap(), timestamp);
+
+    let mut region = Region::open(dir.join("r.0.0.mca")).unwrap();
+    for x in 0..2 {
+        let value = region.read_chunk(x, 0).unwrap().unwrap();
+        assert_eq!(snbt::to_string(&value), snbt::to_string(&chunk(x, 0, 10)));
+    }
+    assert!(region.read_chunk(2, 0).unwrap().is_none());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_region_external() {
+    let dir = temp_dir("external");
+    let mut region = Region::open(dir.join("r.1.-1.mca")).unwrap();
+    let big = chunk(3, 4, 256 * SECTOR_SIZE);
+    region.write_chunk_with(3, 4, &big, Compression::None).unwrap();
+    let external = dir.join("c.35.-28.mcc");
+    assert!(external.exists());
+    assert_eq!(region.sectors(), 3);
+    let value = region.read_chunk(3, 4).unwrap().unwrap();
+    assert_eq!(snbt::to_string(&value), snbt::to_string(&big));
+
+    region.write_chunk(3, 4, &chunk(3, 4, 10)).unwrap();
+    assert!(!external.exists());
+    assert_eq!(snbt::to_string(&region.read_chunk(3, 4).unwrap().unwrap()), snbt::to_string(&chunk(3, 4, 10)));
+
+    // without region coordinates there is nowhere to put the external file
+    let mut unnamed = Region::open(dir.join("region.mca")).unwrap();
+    assert!(matches!(unnamed.write_chunk_with(0, 0, &big, Compression::None), Err(Error::Region(_))));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_region_corrupt() {
+    let dir = temp_dir("corrupt");
+    let path = dir.join("r.0.0.mca");
+    let mut data = vec![0u8; 3 * SECTOR_SIZE];
+    data[..4].copy_from_slice(&((2 << 8) | 1u32).to_be_bytes());
+    data[2 * SECTOR_SIZE..2 * SECTOR_SIZE + 5].copy_from_slice(&[0, 0, 0, 1, 9]);
+    data[4..8].copy_from_slice(&((9 << 8) | 1u32).to_be_bytes());
+    fs::write(&path, data).unwrap();
+
+    let mut region = Region::open(&path).unwrap();
+    assert!(matches!(region.read_chunk(0, 0), Err(Error::Region(_))));
+    assert!(matches!(region.read_chunk(1, 0), Err(Error::Region(_))));
+
+    // coordinates outside the region are errors rather than panics
+    assert!(matches!(region.contains(32, 0), Err(Error::Region(_))));
+    assert!(matches!(region.timestamp(0, 32), Err(Error::Region(_))));
+    assert!(matches!(region.read_chunk(40, 40), Err(Error::Region(_))));
+    assert!(matches!(region.write_chunk(32, 0, &chunk(0, 0, 1)), Err(Error::Region(_))));
+    assert!(matches!(region.remove_chunk(0, 99), Err(Error::Region(_))));
+
+    // a header cut short is not silently zero-filled
+    fs::write(&path, vec![1u8; SECTOR_SIZE]).unwrap();
+    assert!(matches!(Region::open(&path), Err(Error::Region(_))));
+    assert_eq!(fs::metadata(&path).unwrap().len(), SECTOR_SIZE as u64);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,187 @@
+use std::{collections::BTreeMap, fs, io::Read};
+
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+
+use crate::{from_slice, from_value, ser, to_bytes, to_value, Error, Nbt, NbtAfter764, Value};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Player {
+    data_version: i32,
+    pos: Vec<f64>,
+    on_ground: bool,
+    inventory: Vec<Item>,
+    #[serde(rename = "UUID", serialize_with = "ser::int_array")]
+    uuid: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    custom_name: Option<String>,
+    spawn_x: Option<i32>,
+    game_type: GameType,
+    #[serde(serialize_with = "ser::long_array")]
+    seen_credits: Vec<i64>,
+    #[serde(serialize_with = "ser::byte_array")]
+    locked: Vec<i8>,
+    recent: Vec<i32>,
+    abilities: BTreeMap<String, u8>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Item {
+    #[serde(rename = "Slot")]
+    slot: i8,
+    id: String,
+    #[serde(rename = "Count")]
+    count: u8,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum GameType {
+    Survival,
+    Creative,
+    Spectator { flying: bool },
+}
+
+fn player() -> Player {
+    Player {
+        data_version: 3700,
+        pos: vec![0.5, 64.0, -12.25],
+        on_ground: true,
+        inventory: vec![
+            Item { slot: 0, id: "minecraft:diamond_sword".to_string(), count: 1 },
+            Item { slot: -106, id: "minecraft:torch".to_string(), count: 200 },
+        ],
+        uuid: vec![1, -2, 3, -4],
+        custom_name: None,
+        spawn_x: Some(-40),
+        game_type: GameType::Spectator { flying: true },
+        seen_credits: vec![i64::MIN, i64::MAX],
+        locked: vec![1, 0, -1],
+        recent: vec![3, 4],
+        abilities: BTreeMap::from([("flying".to_string(), 1), ("mayfly".to_string(), 0)]),
+    }
+}
+
+#[test]
+fn test_serde_roundtrip() {
+    let player = player();
+    let bytes = to_bytes::<Nbt, _>(&player).unwrap();
+    assert_eq!(&bytes[..3], &[10, 0, 0]);
+    assert_eq!(from_slice::<Nbt, Player>(&bytes).unwrap(), player);
+
+    let bytes = to_bytes::<NbtAfter764, _>(&player).unwrap();
+    assert_eq!(&bytes[..2], &[10, 3]);
+    assert_eq!(from_slice::<NbtAfter764, Player>(&bytes).unwrap(), player);
+}
+
+#[test]
+fn test_serde_tags() {
+    let Value::Compound(entries) = to_value(&player()).unwrap() else { panic!() };
+    let tags: Vec<(&str, u8)> = entries.iter().map(|(k, v)| (k.as_str(), v.tag())).collect();
+    assert_eq!(tags, [
+        ("DataVersion", 3),
+        ("Pos", 9),
+        ("OnGround", 1),
+        ("Inventory", 9),
+        ("UUID", 11),
+        ("SpawnX", 3),
+        ("GameType", 10),
+        ("SeenCredits", 12),
+        ("Locked", 7),
+        ("Recent", 9),
+        ("Abilities", 10),
+    ]);
+    assert!(matches!(to_value(&Some(1)), Ok(Value::Int(1))));
+    assert!(matches!(to_value(&vec![Some(1), None]), Err(Error::Custom(_))));
+    assert!(matches!(to_value(&GameType::Creative), Ok(Value::String(s)) if s == "Creative"));
+}
+
+#[derive(Debug, Deserialize)]
+struct BigTest {
+    #[serde(rename = "longTest")]
+    long_test: i64,
+    #[serde(rename = "stringTest")]
+    string_test: String,
+    #[serde(rename = "nested compound test")]
+    nested: BTreeMap<String, Food>,
+    #[serde(rename = "listTest (long)")]
+    list_long: Vec<i64>,
+    #[serde(rename = "byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))")]
+    byte_array: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Food {
+    name: String,
+    value: f32,
+}
+
+#[test]
+fn test_serde_bigtest() {
+    let data1 = fs::read("src/test/bigtest.nbt").unwrap();
+    let mut d = GzDecoder::new(&data1[..]);
+    let mut data = Vec::new();
+    d.read_to_end(&mut data).unwrap();
+    let value: BigTest = from_slice::<Nbt, _>(&data).unwrap();
+    assert_eq!(value.long_test, i64::MAX);
+    assert_eq!(value.string_test, "HELLO WORLD THIS IS A TEST STRING ÅÄÖ!");
+    assert_eq!(value.nested["egg"].name, "Eggbert");
+    assert_eq!(value.nested["ham"].value, 0.75);
+    assert_eq!(value.list_long, [11, 12, 13, 14, 15]);
+    assert_eq!(value.byte_array.len(), 1000);
+    assert_eq!(value.byte_array[1], 62);
+
+    assert!(from_value::<Food>(Value::Int(1)).is_err());
+}
+
+#[test]
+fn test_serde_sequences_are_lists() {
+    let bytes = vec![1u8, 127, 128, 200, 255];
+    let list = |values: Vec<i8>| Value::List(values.into_iter().map(Value::Byte).collect());
+    assert_eq!(to_value(&bytes).unwrap(), list(vec![1, 127, -128, -56, -1]));
+    assert_eq!(from_value::<Vec<u8>>(to_value(&bytes).unwrap()).unwrap(), bytes);
+    assert_eq!(from_value::<Vec<u8>>(Value::ByteArray(vec![1, -1])).unwrap(), [1, 255]);
+
+    let bools = vec![true, false, true];
+    assert_eq!(to_value(&bools).unwrap(), list(vec![1, 0, 1]));
+    assert_eq!(from_value::<Vec<bool>>(to_value(&bools).unwrap()).unwrap(), bools);
+
+    // tuples and plain vectors of ints stay lists too
+    assert_eq!(to_value(&(1i32, 2i32)).unwrap(), Value::List(vec![Value::Int(1), Value::Int(2)]));
+    assert_eq!(to_value(&vec![7i64]).unwrap(), Value::List(vec![Value::Long(7)]));
+
+    let ints = vec![0, 1 << 31, u32::MAX];
+    assert_eq!(to_value(&ints).unwrap().tag(), 9);
+    assert_eq!(from_value::<Vec<u32>>(to_value(&ints).unwrap()).unwrap(), ints);
+    assert_eq!(from_value::<Vec<u32>>(Value::IntArray(vec![-1])).unwrap(), [u32::MAX]);
+    let longs = vec![u64::MAX, 7];
+    assert_eq!(from_value::<Vec<u64>>(to_value(&longs).unwrap()).unwrap(), longs);
+
+    let empty: Vec<i32> = Vec::new();
+    assert_eq!(to_value(&empty).unwrap(), Value::List(Vec::new()));
+    assert_eq!(from_value::<Vec<i32>>(to_value(&empty).unwrap()).unwrap(), empty);
+    assert_eq!(from_value::<Vec<i32>>(Value::IntArray(Vec::new())).unwrap(), empty);
+
+    // through the binary format too
+    let bytes = to_bytes::<Nbt, _>(&BTreeMap::from([("b".to_string(), vec![200u8, 255])])).unwrap();
+    assert_eq!(from_slice::<Nbt, BTreeMap<String, Vec<u8>>>(&bytes).unwrap()["b"], [200, 255]);
+}
+
+#[derive(Serialize)]
+struct Arrays {
+    #[serde(serialize_with = "ser::byte_array")]
+    bytes: Vec<i8>,
+    #[serde(serialize_with = "ser::int_array")]
+    ints: Vec<i32>,
+    #[serde(serialize_with = "ser::long_array")]
+    longs: Vec<i64>,
+}
+
+#[test]
+fn test_serde_array_helpers() {
+    let arrays = Arrays { bytes: vec![-1], ints: Vec::new(), longs: vec![i64::MIN] };
+    let Value::Compound(entries) = to_value(&arrays).unwrap() else { panic!() };
+    assert_eq!(entries.get("bytes"), Some(&Value::ByteArray(vec![-1])));
+    assert_eq!(entries.get("ints"), Some(&Value::IntArray(Vec::new())));
+    assert_eq!(entries.get("longs"), Some(&Value::LongArray(vec![i64::MIN])));
+}
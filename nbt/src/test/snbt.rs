@@ -0,0 +1,97 @@
+use std::{fs, io::Read};
+
+use flate2::read::GzDecoder;
+
+use crate::{reader::NbtReader, snbt, writer::NbtWriter, Error, Nbt, NbtReadTrait, NbtWriteTrait, Value};
+
+fn roundtrip(s: &str) -> String {
+    snbt::to_string(&snbt::from_str(s).unwrap())
+}
+
+#[test]
+fn test_snbt_types() {
+    let value = snbt::from_str("{a:1b,b:2s,c:3,d:4L,e:5.5f,f:6.25d,g:7.0,h:true,i:false,j:'x',k:\"y\",l:abc}").unwrap();
+    let Value::Compound(entries) = &value else { panic!() };
+    let tags: Vec<u8> = entries.iter().map(|(_, v)| v.tag()).collect();
+    assert_eq!(tags, [1, 2, 3, 4, 5, 6, 6, 1, 1, 8, 8, 8]);
+    assert_eq!(snbt::to_string(&value), "{a:1b,b:2s,c:3,d:4L,e:5.5f,f:6.25d,g:7.0d,h:1b,i:0b,j:\"x\",k:\"y\",l:\"abc\"}");
+
+    // vanilla falls back to strings for anything that doesn't fit its numeric patterns
+    assert!(matches!(snbt::from_str("128b").unwrap(), Value::String(s) if s == "128b"));
+    assert!(matches!(snbt::from_str("01").unwrap(), Value::String(s) if s == "01"));
+    assert!(matches!(snbt::from_str("2147483648").unwrap(), Value::String(_)));
+    assert!(matches!(snbt::from_str("1e3").unwrap(), Value::String(_)));
+    assert!(matches!(snbt::from_str("1.5e3").unwrap(), Value::Double(v) if v == 1500.0));
+    assert!(matches!(snbt::from_str("-.5F").unwrap(), Value::Float(v) if v == -0.5));
+    assert!(matches!(snbt::from_str("+3S").unwrap(), Value::Short(3)));
+}
+
+#[test]
+fn test_snbt_arrays_and_lists() {
+    assert_eq!(roundtrip("[B; 1b, -2B]"), "[B;1B,-2B]");
+    assert_eq!(roundtrip("[I;1,2,3]"), "[I;1,2,3]");
+    assert_eq!(roundtrip("[L; 1l, 2L ,]"), "[L;1L,2L]");
+    assert_eq!(roundtrip("[I;]"), "[I;]");
+    assert_eq!(roundtrip("[]"), "[]");
+    assert_eq!(roundtrip("[[],[1,2],[{}]]"), "[[],[1,2],[{}]]");
+    assert_eq!(roundtrip(" [ {a : 1} , { b:2 } ] "), "[{a:1},{b:2}]");
+    assert!(matches!(snbt::from_str("[1, 2b]"), Err(Error::Snbt { offset: 4, .. })));
+    assert!(matches!(snbt::from_str("[I; 1, 2L]"), Err(Error::Snbt { offset: 7, .. })));
+    assert!(matches!(snbt::from_str("[X; 1]"), Err(Error::Snbt { offset: 1, .. })));
+}
+
+#[test]
+fn test_snbt_strings_and_keys() {
+    assert_eq!(roundtrip(r#"{"a b":'it\'s',"c\"d":"say \"hi\"",'':"\\"}"#), r#"{"a b":"it's",'c"d':'say "hi"',"":"\\"}"#);
+    assert_eq!(roundtrip("{minecraft.id-x_1+:\"\"}"), "{minecraft.id-x_1+:\"\"}");
+    assert!(matches!(snbt::from_str(r#""abc"#), Err(Error::Snbt { .. })));
+    assert!(matches!(snbt::from_str(r#""\n""#), Err(Error::Snbt { offset: 2, .. })));
+    assert!(matches!(snbt::from_str("{a:1} x"), Err(Error::Snbt { offset: 6, .. })));
+    assert!(matches!(snbt::from_str("{a:1"), Err(Error::Snbt { .. })));
+    assert!(matches!(snbt::from_str("{:1}"), Err(Error::Snbt { offset: 1, .. })));
+    assert!(matches!(snbt::from_str(&"[".repeat(1000)), Err(Error::DepthLimitExceeded(_))));
+}
+
+#[test]
+fn test_snbt_lossless() {
+    let data1 = fs::read("src/test/bigtest.nbt").unwrap();
+    let mut d = GzDecoder::new(&data1[..]);
+    let mut data = Vec::new();
+    d.read_to_end(&mut data).unwrap();
+    let value = Nbt::from_reader(&mut NbtReader::new(&data)).unwrap();
+    let text = snbt::to_string(&value);
+    let parsed: Value = text.parse().unwrap();
+    assert_eq!(snbt::to_string(&parsed), text);
+    let mut writer = NbtWriter::new();
+    Nbt::write_to(&mut writer, &parsed).unwrap();
+    // the root name isn't part of SNBT
+    assert_eq!(writer.data[3..], data[8..]);
+
+    for v in [f32::MIN_POSITIVE, f32::MAX, 1e-10, 0.1] {
+        assert!(matches!(snbt::from_str(&snbt::to_string(&Value::Float(v))).unwrap(), Value::Float(x) if x == v));
+    }
+    for v in [f64::MIN_POSITIVE, f64::MAX, 1e300, 0.1] {
+        assert!(matches!(snbt::from_str(&snbt::to_string(&Value::Double(v))).unwrap(), Value::Double(x) if x == v));
+    }
+}
+
+#[test]
+fn test_snbt_non_finite() {
+    let values = [
+        (Value::Float(f32::NAN), "NaNf"),
+        (Value::Float(f32::INFINITY), "Infinityf"),
+        (Value::Float(f32::NEG_INFINITY), "-Infinityf"),
+        (Value::Double(f64::NAN), "NaNd"),
+        (Value::Double(f64::INFINITY), "Infinityd"),
+        (Value::Double(f64::NEG_INFINITY), "-Infinityd"),
+    ];
+    for (value, text) in values {
+        assert_eq!(snbt::to_string(&value), text);
+        assert_eq!(snbt::from_str(text).unwrap(), value);
+        let list = Value::List(vec![value.clone(), value]);
+        assert_eq!(snbt::from_str(&snbt::to_string_pretty(&list)).unwrap(), list);
+    }
+    // only the names written above are numbers; the rest stay strings as in vanilla
+    assert_eq!(snbt::from_str("inff").unwrap(), Value::String("inff".to_string()));
+    assert_eq!(snbt::from_str("nand").unwrap(), Value::String("nand".to_string()));
+}
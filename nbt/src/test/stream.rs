@@ -0,0 +1,99 @@
+use std::{fs, io::{self, Read}};
+
+use flate2::read::GzDecoder;
+
+use crate::{
+    reader::{i32s_from_be_bytes, i64s_from_be_bytes, NbtLimits, NbtReader, NbtStreamReader},
+    snbt, writer::{extend_be_i32s, extend_be_i64s, NbtStreamWriter, NbtWriter}, Error, Nbt, NbtAfter764, NbtReadTrait,
+    NbtWriteTrait, Value,
+};
+
+fn bigtest() -> Vec<u8> {
+    let mut data = Vec::new();
+    GzDecoder::new(&fs::read("src/test/bigtest.nbt").unwrap()[..]).read_to_end(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_stream_gzip() {
+    let file = fs::File::open("src/test/bigtest.nbt").unwrap();
+    let mut reader = NbtStreamReader::buffered(GzDecoder::new(file));
+    let streamed = Nbt::from_reader(&mut reader).unwrap();
+    let data = bigtest();
+    assert_eq!(reader.position(), data.len());
+
+    let mut slice_reader = NbtReader::new(&data);
+    let value = Nbt::from_reader(&mut slice_reader).unwrap();
+    assert_eq!(snbt::to_string(&streamed), snbt::to_string(&value));
+    assert_eq!(reader.accounted(), slice_reader.accounted());
+}
+
+#[test]
+fn test_stream_truncated() {
+    let data = bigtest();
+    for len in 0..data.len() {
+        let mut reader = NbtStreamReader::new(&data[..len]);
+        match Nbt::from_reader(&mut reader) {
+            Err(Error::UnexpectedEof { offset, needed }) => assert!(offset + needed > len),
+            x => panic!("prefix of {} bytes: expected UnexpectedEof, got {:?}", len, x),
+        }
+    }
+}
+
+#[test]
+fn test_stream_consecutive_documents() {
+    // network packets carry several nameless compounds back to back
+    let data = [10, 3, 0, 1, b'a', 0, 0, 0, 1, 0, 10, 0];
+    let mut reader = NbtStreamReader::new(&data[..]);
+    assert_eq!(snbt::to_string(&NbtAfter764::from_reader(&mut reader).unwrap()), "{a:1}");
+    assert_eq!(snbt::to_string(&NbtAfter764::from_reader(&mut reader).unwrap()), "{}");
+    assert!(reader.into_inner().is_empty());
+}
+
+struct Failing;
+
+impl Read for Failing {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+    }
+}
+
+#[test]
+fn test_stream_errors() {
+    let mut reader = NbtStreamReader::new(Failing);
+    assert!(matches!(Nbt::from_reader(&mut reader), Err(Error::Io(e)) if e.kind() == io::ErrorKind::ConnectionReset));
+
+    // a long array declaring i32::MAX elements fails without allocating them; arrays are read
+    // whole, so the error is reported at the start of the elements
+    let data = [10, 0, 0, 12, 0, 0, 0x7f, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 1];
+    let mut reader = NbtStreamReader::new(&data[..]);
+    assert!(matches!(Nbt::from_reader(&mut reader), Err(Error::UnexpectedEof { offset: 10, needed }) if needed == 0x7fff_ffff * 8));
+    let mut reader = NbtStreamReader::with_limits(&data[..], NbtLimits::network());
+    assert!(matches!(Nbt::from_reader(&mut reader), Err(Error::SizeLimitExceeded { .. })));
+}
+
+#[test]
+fn test_bulk_arrays() {
+    let ints: Vec<i32> = (0..1001).map(|i: i32| i.wrapping_mul(0x01020304) ^ -i).collect();
+    let longs: Vec<i64> = (0..1001).map(|i: i64| i.wrapping_mul(0x0102030405060708) ^ -i).collect();
+
+    let mut bytes = vec![0xff];
+    extend_be_i32s(&mut bytes, &ints);
+    assert_eq!(bytes[1..], ints.iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<_>>());
+    assert_eq!(i32s_from_be_bytes(&bytes[1..]), ints);
+    // a partial trailing element is ignored
+    assert_eq!(i32s_from_be_bytes(&bytes[1..bytes.len() - 1]), ints[..1000]);
+    let mut bytes = Vec::new();
+    extend_be_i64s(&mut bytes, &longs);
+    assert_eq!(bytes, longs.iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<_>>());
+    assert_eq!(i64s_from_be_bytes(&bytes), longs);
+
+    let value = Value::Compound([("i".to_string(), Value::IntArray(ints)), ("l".to_string(), Value::LongArray(longs))].into_iter().collect());
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &value).unwrap();
+    let mut stream = NbtStreamWriter::new(Vec::new());
+    Nbt::write_to(&mut stream, &value).unwrap();
+    assert_eq!(stream.into_inner(), w.data);
+    assert_eq!(Nbt::from_reader(&mut NbtReader::new(&w.data)).unwrap(), value);
+    assert_eq!(Nbt::from_reader(&mut NbtStreamReader::new(&w.data[..])).unwrap(), value);
+}
@@ -0,0 +1,43 @@
+use crate::{snbt, TreeOptions, Value};
+
+#[test]
+fn test_tree() {
+    let value = snbt::from_str("{name:\"Bananrama\",pos:[0.5d,64.0d],inv:[{id:\"a\",n:1b}],bytes:[B;1b,2b,3b,4b],none:[],e:{}}").unwrap();
+    assert_eq!(
+        value.to_string(),
+        "Compound (6 entries)\n\
+         ├── name: String \"Bananrama\"\n\
+         ├── pos: List of Double (2 entries)\n\
+         │   ├── [0]: Double 0.5\n\
+         │   └── [1]: Double 64\n\
+         ├── inv: List of Compound (1 entry)\n\
+         │   └── [0]: Compound (2 entries)\n\
+         │       ├── id: String \"a\"\n\
+         │       └── n: Byte 1\n\
+         ├── bytes: ByteArray (4 entries) [1, 2, 3, 4]\n\
+         ├── none: List (0 entries)\n\
+         └── e: Compound (0 entries)"
+    );
+
+    let array = Value::IntArray((0..100).collect());
+    let options = TreeOptions { max_array_len: 3, ..TreeOptions::default() };
+    assert_eq!(array.tree(options).to_string(), "IntArray (100 entries) [0, 1, 2, … 97 more]");
+    let options = TreeOptions { color: true, max_array_len: 0 };
+    assert_eq!(array.tree(options).to_string(), "\x1b[34mIntArray\x1b[0m \x1b[2m(100 entries)\x1b[0m [\x1b[2m… 100 more\x1b[0m]");
+}
+
+#[test]
+fn test_pretty_snbt() {
+    let value = snbt::from_str("{a:{b:[1,2],c:[{d:1b},{}]},e:[I;1,2],f:{},g:[[]]}").unwrap();
+    let pretty = format!("{:#}", value);
+    assert_eq!(
+        pretty,
+        "{\n    a: {\n        b: [1,2],\n        c: [\n            {\n                d: 1b\n            },\n            {}\n        ]\n    },\n    e: [I;1,2],\n    f: {},\n    g: [\n        []\n    ]\n}"
+    );
+    assert_eq!(snbt::from_str(&pretty).unwrap(), value);
+    assert_eq!(format!("{:#}", Value::Int(1)), "1");
+
+    assert_eq!(Value::Int(5).value(), "5");
+    assert_eq!(Value::String("x y".to_string()).value(), "x y");
+    assert_eq!(value.get("a").unwrap().value(), "{b:[1,2],c:[{d:1b},{}]}");
+}
@@ -0,0 +1,110 @@
+use std::{fs, io::Read};
+
+use flate2::read::GzDecoder;
+
+use crate::{reader::{NbtLimits, NbtReader}, snbt, writer::NbtWriter, Error, Nbt, NbtAfter764, NbtReadTrait, NbtWriteTrait, ValueRef};
+
+fn bigtest() -> Vec<u8> {
+    let mut data = Vec::new();
+    GzDecoder::new(&fs::read("src/test/bigtest.nbt").unwrap()[..]).read_to_end(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_value_ref_fields() {
+    let data = bigtest();
+    let root = Nbt::from_reader_ref(&mut NbtReader::new(&data)).unwrap();
+
+    assert!(matches!(root.get("longTest"), Some(ValueRef::Long(i64::MAX))));
+    assert!(root.get("missing").is_none());
+    let Some(ValueRef::String(s)) = root.get("stringTest") else { panic!() };
+    assert_eq!(s, "HELLO WORLD THIS IS A TEST STRING ÅÄÖ!");
+    // the string is borrowed straight from the input buffer
+    let offset = s.as_bytes().as_ptr() as usize - data.as_ptr() as usize;
+    assert_eq!(&data[offset..offset + s.as_bytes().len()], s.as_bytes());
+
+    let egg = root.get("nested compound test").and_then(|v| v.get("egg")).unwrap();
+    assert!(matches!(egg.get("value"), Some(ValueRef::Float(v)) if v == 0.5));
+
+    let list = root.get("listTest (compound)").and_then(|v| v.as_list()).unwrap();
+    assert_eq!((list.element_tag(), list.len()), (10, 2));
+    let names: Vec<String> = list.iter().map(|c| match c.get("name") {
+        Some(ValueRef::String(s)) => s.to_str().unwrap().into_owned(),
+        x => panic!("{:?}", x),
+    }).collect();
+    assert_eq!(names, ["Compound tag #0", "Compound tag #1"]);
+    assert!(matches!(list.get(1).and_then(|c| c.get("created-on")), Some(ValueRef::Long(1264099775885))));
+    assert!(list.get(2).is_none());
+
+    let key = root.as_compound().unwrap().iter().map(|(k, _)| k).find(|k| k.as_bytes().starts_with(b"byteArray")).unwrap();
+    let Some(ValueRef::ByteArray(bytes)) = root.get(&key.to_str().unwrap()) else { panic!() };
+    assert_eq!(bytes.len(), 1000);
+}
+
+#[test]
+fn test_value_ref_arrays() {
+    let value = snbt::from_str("{a:[I;1,-2,2147483647],b:[L;-1L,9223372036854775807L],c:[I;]}").unwrap();
+    let mut w = NbtWriter::new();
+    NbtAfter764::write_to(&mut w, &value).unwrap();
+    let root = NbtAfter764::from_reader_ref(&mut NbtReader::new(&w.data)).unwrap();
+
+    let Some(ValueRef::IntArray(a)) = root.get("a") else { panic!() };
+    assert_eq!(a.len(), 3);
+    assert_eq!(a.get(1), Some(-2));
+    assert_eq!(a.get(3), None);
+    assert_eq!(a.iter().collect::<Vec<_>>(), [1, -2, i32::MAX]);
+    let Some(ValueRef::LongArray(b)) = root.get("b") else { panic!() };
+    assert_eq!(b.to_vec(), [-1, i64::MAX]);
+    let Some(ValueRef::IntArray(c)) = root.get("c") else { panic!() };
+    assert!(c.is_empty());
+}
+
+#[test]
+fn test_value_ref_iter_exhausted() {
+    let data = bigtest();
+    let root = Nbt::from_reader_ref(&mut NbtReader::new(&data)).unwrap();
+    let mut entries = root.as_compound().unwrap().iter();
+    assert_eq!(entries.by_ref().count(), 11);
+    assert!(entries.next().is_none());
+    assert!(entries.next().is_none());
+
+    let list = root.get("listTest (long)").and_then(|v| v.as_list()).unwrap();
+    let mut elements = list.iter();
+    assert_eq!(elements.by_ref().count(), 5);
+    assert!(elements.next().is_none());
+    // zip asks the longer side for one more element after the shorter runs out
+    assert_eq!(list.iter().zip(root.as_compound().unwrap()).count(), 5);
+    assert_eq!(root.as_compound().unwrap().iter().zip(0..20).count(), 11);
+}
+
+#[test]
+fn test_value_ref_to_owned() {
+    let data = bigtest();
+    let owned = Nbt::from_reader(&mut NbtReader::new(&data)).unwrap();
+    let converted = Nbt::from_reader_ref(&mut NbtReader::new(&data)).unwrap().to_owned().unwrap();
+    assert_eq!(snbt::to_string(&converted), snbt::to_string(&owned));
+    let mut w = NbtWriter::new();
+    Nbt::write_to(&mut w, &converted).unwrap();
+    assert_eq!(w.data[3..], data[8..]);
+}
+
+#[test]
+fn test_value_ref_invalid() {
+    let data = bigtest();
+    for len in 0..data.len() {
+        match Nbt::from_reader_ref(&mut NbtReader::new(&data[..len])) {
+            Err(Error::UnexpectedEof { offset, needed }) => assert!(offset + needed > len),
+            x => panic!("prefix of {} bytes: expected UnexpectedEof, got {:?}", len, x),
+        }
+    }
+
+    let mut deep = vec![10, 0, 0, 9, 0, 0];
+    for _ in 0..NbtLimits::MAX_DEPTH {
+        deep.extend_from_slice(&[9, 0, 0, 0, 1]);
+    }
+    deep.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    assert!(matches!(Nbt::from_reader_ref(&mut NbtReader::new(&deep)), Err(Error::DepthLimitExceeded(_))));
+
+    let bad = [10, 0, 0, 9, 0, 1, b'x', 13, 0, 0, 0, 1, 0];
+    assert!(matches!(Nbt::from_reader_ref(&mut NbtReader::new(&bad)), Err(Error::InvalidTag(13))));
+}
@@ -0,0 +1,150 @@
+use std::fmt::{self, Write};
+
+use crate::{snbt, Value};
+
+/// How [`Value::tree`] renders a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeOptions {
+    /// Colour type names, keys and values with ANSI escape codes.
+    pub color: bool,
+    /// How many array elements are shown before the rest are counted instead.
+    pub max_array_len: usize,
+}
+
+impl Default for TreeOptions {
+    fn default() -> TreeOptions {
+        TreeOptions { color: false, max_array_len: 16 }
+    }
+}
+
+/// The name NBTExplorer and the wiki use for a tag type, without the `TAG_` prefix.
+pub fn type_name(tag: u8) -> &'static str {
+    match tag {
+        0 => "End",
+        1 => "Byte",
+        2 => "Short",
+        3 => "Int",
+        4 => "Long",
+        5 => "Float",
+        6 => "Double",
+        7 => "ByteArray",
+        8 => "String",
+        9 => "List",
+        10 => "Compound",
+        11 => "IntArray",
+        12 => "LongArray",
+        _ => "Unknown",
+    }
+}
+
+const TYPE: &str = "34";
+const KEY: &str = "1";
+const COUNT: &str = "2";
+const NUMBER: &str = "33";
+const STRING: &str = "32";
+
+/// A tree rendering of a [`Value`], one tag per line:
+///
+/// ```text
+/// Compound (2 entries)
+/// ├── name: String "Bananrama"
+/// └── pos: List of Double (3 entries)
+///     ├── [0]: Double 0.5
+///     ...
+/// ```
+pub struct Tree<'a> {
+    value: &'a Value,
+    options: TreeOptions,
+}
+
+impl<'a> Tree<'a> {
+    pub fn new(value: &'a Value, options: TreeOptions) -> Tree<'a> {
+        Tree { value, options }
+    }
+
+    fn paint<W: Write>(&self, w: &mut W, code: &str, text: impl fmt::Display) -> fmt::Result {
+        if self.options.color {
+            write!(w, "\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            write!(w, "{}", text)
+        }
+    }
+
+    fn count<W: Write>(&self, w: &mut W, len: usize) -> fmt::Result {
+        let text = format!("({} {})", len, if len == 1 { "entry" } else { "entries" });
+        self.paint(w, COUNT, text)
+    }
+
+    fn array<W: Write, T: fmt::Display>(&self, w: &mut W, values: &[T]) -> fmt::Result {
+        self.count(w, values.len())?;
+        w.write_str(" [")?;
+        let shown = values.len().min(self.options.max_array_len);
+        for (i, v) in values[..shown].iter().enumerate() {
+            if i > 0 {
+                w.write_str(", ")?;
+            }
+            self.paint(w, NUMBER, v)?;
+        }
+        if shown < values.len() {
+            if shown > 0 {
+                w.write_str(", ")?;
+            }
+            self.paint(w, COUNT, format!("… {} more", values.len() - shown))?;
+        }
+        w.write_char(']')
+    }
+
+    // The line describing `value` itself, after its key.
+    fn header<W: Write>(&self, w: &mut W, value: &Value) -> fmt::Result {
+        match value {
+            Value::List(v) if !v.is_empty() => {
+                self.paint(w, TYPE, format!("List of {}", type_name(v[0].tag())))?;
+            }
+            v => self.paint(w, TYPE, type_name(v.tag()))?,
+        }
+        w.write_char(' ')?;
+        match value {
+            Value::Byte(v) => self.paint(w, NUMBER, v),
+            Value::Short(v) => self.paint(w, NUMBER, v),
+            Value::Int(v) => self.paint(w, NUMBER, v),
+            Value::Long(v) => self.paint(w, NUMBER, v),
+            Value::Float(v) => self.paint(w, NUMBER, v),
+            Value::Double(v) => self.paint(w, NUMBER, v),
+            Value::String(_) => self.paint(w, STRING, snbt::to_string(value)),
+            Value::ByteArray(v) => self.array(w, v),
+            Value::IntArray(v) => self.array(w, v),
+            Value::LongArray(v) => self.array(w, v),
+            Value::List(v) => self.count(w, v.len()),
+            Value::Compound(v) => self.count(w, v.len()),
+        }
+    }
+
+    fn children<W: Write>(&self, w: &mut W, value: &Value, prefix: &mut String) -> fmt::Result {
+        let entries: Vec<(String, &Value)> = match value {
+            Value::List(v) => v.iter().enumerate().map(|(i, v)| (format!("[{}]", i), v)).collect(),
+            Value::Compound(v) => v.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            _ => return Ok(()),
+        };
+        for (i, (key, v)) in entries.iter().enumerate() {
+            let last = i + 1 == entries.len();
+            w.write_char('\n')?;
+            w.write_str(prefix)?;
+            w.write_str(if last { "└── " } else { "├── " })?;
+            self.paint(w, KEY, key)?;
+            w.write_str(": ")?;
+            self.header(w, v)?;
+            let len = prefix.len();
+            prefix.push_str(if last { "    " } else { "│   " });
+            self.children(w, v, prefix)?;
+            prefix.truncate(len);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Tree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.header(f, self.value)?;
+        self.children(f, self.value, &mut String::new())
+    }
+}
@@ -0,0 +1,399 @@
+use std::borrow::Cow;
+use std::iter::FusedIterator;
+
+use crate::reader::{NbtLimits, NbtReader, NbtSource, COMPOUND_ENTRY_SIZE, TAG_SIZES};
+use crate::{mutf8, Compound, Error, Result, Value};
+
+/// A view of an NBT value that borrows strings, arrays and nested tags from the input.
+///
+/// Reading one only validates the structure; compounds and lists are decoded again
+/// on each iteration, and array elements are decoded on access. No index of entries is kept, so
+/// for many lookups in the same large tree [`to_owned`](ValueRef::to_owned) is cheaper.
+#[derive(Debug, Clone, Copy)]
+pub enum ValueRef<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(&'a [u8]),
+    String(StrRef<'a>),
+    List(ListRef<'a>),
+    Compound(CompoundRef<'a>),
+    IntArray(IntArrayRef<'a>),
+    LongArray(LongArrayRef<'a>),
+}
+
+impl<'a> ValueRef<'a> {
+    pub fn tag(&self) -> u8 {
+        match self {
+            ValueRef::Byte(_) => 1,
+            ValueRef::Short(_) => 2,
+            ValueRef::Int(_) => 3,
+            ValueRef::Long(_) => 4,
+            ValueRef::Float(_) => 5,
+            ValueRef::Double(_) => 6,
+            ValueRef::ByteArray(_) => 7,
+            ValueRef::String(_) => 8,
+            ValueRef::List(_) => 9,
+            ValueRef::Compound(_) => 10,
+            ValueRef::IntArray(_) => 11,
+            ValueRef::LongArray(_) => 12,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<ValueRef<'a>> {
+        match self {
+            ValueRef::Compound(c) => c.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<CompoundRef<'a>> {
+        match self {
+            ValueRef::Compound(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<ListRef<'a>> {
+        match self {
+            ValueRef::List(l) => Some(*l),
+            _ => None,
+        }
+    }
+
+    pub fn to_owned(&self) -> Result<Value> {
+        Ok(match self {
+            ValueRef::Byte(v) => Value::Byte(*v),
+            ValueRef::Short(v) => Value::Short(*v),
+            ValueRef::Int(v) => Value::Int(*v),
+            ValueRef::Long(v) => Value::Long(*v),
+            ValueRef::Float(v) => Value::Float(*v),
+            ValueRef::Double(v) => Value::Double(*v),
+            ValueRef::ByteArray(v) => Value::ByteArray(v.iter().map(|&b| b as i8).collect()),
+            ValueRef::String(v) => Value::String(v.to_str()?.into_owned()),
+            ValueRef::List(v) => Value::List(v.iter().map(|v| v.to_owned()).collect::<Result<_>>()?),
+            ValueRef::Compound(v) => {
+                let mut entries = Compound::new();
+                for (key, value) in v.iter() {
+                    let key = key.to_str()?.into_owned();
+                    entries.insert(key, value.to_owned()?);
+                }
+                Value::Compound(entries)
+            }
+            ValueRef::IntArray(v) => Value::IntArray(v.to_vec()),
+            ValueRef::LongArray(v) => Value::LongArray(v.to_vec()),
+        })
+    }
+}
+
+/// A string still in its Modified UTF-8 encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrRef<'a>(pub &'a [u8]);
+
+impl<'a> StrRef<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn to_str(&self) -> Result<Cow<'a, str>> {
+        mutf8::decode(self.0)
+    }
+}
+
+impl PartialEq<str> for StrRef<'_> {
+    fn eq(&self, other: &str) -> bool {
+        *mutf8::encode(other) == *self.0
+    }
+}
+
+impl PartialEq<&str> for StrRef<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompoundRef<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> CompoundRef<'a> {
+    pub fn iter(&self) -> CompoundIter<'a> {
+        CompoundIter { r: NbtReader::with_limits(self.data, NbtLimits::unlimited()), done: false }
+    }
+
+    /// Scans the entries up to `key`, walking through every nested list and compound before it
+    /// to find where it ends, so a lookup costs as much as the tags in front of the entry. Chained
+    /// lookups such as `get("a")?.get("b")` pay that again at each level.
+    pub fn get(&self, key: &str) -> Option<ValueRef<'a>> {
+        let key = mutf8::encode(key);
+        self.iter().find(|(k, _)| k.0 == &*key).map(|(_, v)| v)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.len() <= 1
+    }
+}
+
+impl<'a> IntoIterator for CompoundRef<'a> {
+    type Item = (StrRef<'a>, ValueRef<'a>);
+    type IntoIter = CompoundIter<'a>;
+
+    fn into_iter(self) -> CompoundIter<'a> {
+        self.iter()
+    }
+}
+
+pub struct CompoundIter<'a> {
+    r: NbtReader<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for CompoundIter<'a> {
+    type Item = (StrRef<'a>, ValueRef<'a>);
+
+    fn next(&mut self) -> Option<(StrRef<'a>, ValueRef<'a>)> {
+        if self.done {
+            return None;
+        }
+        let entry = read_entry(&mut self.r).expect("compound was validated when it was read");
+        // the End tag has been consumed, so there is nothing left to read
+        self.done = entry.is_none();
+        entry
+    }
+}
+
+impl FusedIterator for CompoundIter<'_> {}
+
+fn read_entry<'a>(r: &mut NbtReader<'a>) -> Result<Option<(StrRef<'a>, ValueRef<'a>)>> {
+    let type_id = r.read_u8()?;
+    if type_id == 0 {
+        return Ok(None);
+    }
+    let key = read_str(r)?;
+    Ok(Some((key, read_payload(r, type_id)?)))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ListRef<'a> {
+    type_id: u8,
+    len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> ListRef<'a> {
+    pub fn element_tag(&self) -> u8 {
+        self.type_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Walks the elements before `index`, like [`CompoundRef::get`].
+    pub fn get(&self, index: usize) -> Option<ValueRef<'a>> {
+        self.iter().nth(index)
+    }
+
+    pub fn iter(&self) -> ListIter<'a> {
+        ListIter {
+            r: NbtReader::with_limits(self.data, NbtLimits::unlimited()),
+            type_id: self.type_id,
+            remaining: self.len,
+        }
+    }
+}
+
+impl<'a> IntoIterator for ListRef<'a> {
+    type Item = ValueRef<'a>;
+    type IntoIter = ListIter<'a>;
+
+    fn into_iter(self) -> ListIter<'a> {
+        self.iter()
+    }
+}
+
+pub struct ListIter<'a> {
+    r: NbtReader<'a>,
+    type_id: u8,
+    remaining: usize,
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = ValueRef<'a>;
+
+    fn next(&mut self) -> Option<ValueRef<'a>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(read_payload(&mut self.r, self.type_id).expect("list was validated when it was read"))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for ListIter<'_> {}
+
+impl FusedIterator for ListIter<'_> {}
+
+macro_rules! array_ref {
+    ($name:ident, $ty:ty, $size:expr) => {
+        /// Big-endian array elements, decoded on access.
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name<'a>(pub &'a [u8]);
+
+        impl<'a> $name<'a> {
+            pub fn len(&self) -> usize {
+                self.0.len() / $size
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+
+            pub fn get(&self, index: usize) -> Option<$ty> {
+                let bytes = self.0.get(index * $size..(index + 1) * $size)?;
+                Some(<$ty>::from_be_bytes(bytes.try_into().unwrap()))
+            }
+
+            pub fn iter(&self) -> impl ExactSizeIterator<Item = $ty> + 'a {
+                self.0.chunks_exact($size).map(|b| <$ty>::from_be_bytes(b.try_into().unwrap()))
+            }
+
+            pub fn to_vec(&self) -> Vec<$ty> {
+                self.iter().collect()
+            }
+        }
+    };
+}
+
+array_ref!(IntArrayRef, i32, 4);
+array_ref!(LongArrayRef, i64, 8);
+
+fn read_str<'a>(r: &mut NbtReader<'a>) -> Result<StrRef<'a>> {
+    let len = r.read_u16()? as usize;
+    r.account(2 * len as u64)?;
+    Ok(StrRef(r.take(len)?))
+}
+
+fn read_payload<'a>(r: &mut NbtReader<'a>, type_id: u8) -> Result<ValueRef<'a>> {
+    Ok(match type_id {
+        1 => ValueRef::Byte(r.read_i8()?),
+        2 => ValueRef::Short(r.read_i16()?),
+        3 => ValueRef::Int(r.read_i32()?),
+        4 => ValueRef::Long(r.read_i64()?),
+        5 => ValueRef::Float(r.read_f32()?),
+        6 => ValueRef::Double(r.read_f64()?),
+        7 => {
+            let len = r.read_len()?;
+            r.account(len as u64)?;
+            ValueRef::ByteArray(r.take(len)?)
+        }
+        8 => ValueRef::String(read_str(r)?),
+        9 => ValueRef::List(read_list(r)?),
+        10 => ValueRef::Compound(read_compound(r)?),
+        11 => {
+            let len = r.read_len()?;
+            r.account(4 * len as u64)?;
+            ValueRef::IntArray(IntArrayRef(r.take(len.saturating_mul(4))?))
+        }
+        12 => {
+            let len = r.read_len()?;
+            r.account(8 * len as u64)?;
+            ValueRef::LongArray(LongArrayRef(r.take(len.saturating_mul(8))?))
+        }
+        _ => return Err(Error::InvalidTag(type_id)),
+    })
+}
+
+fn read_list<'a>(r: &mut NbtReader<'a>) -> Result<ListRef<'a>> {
+    let start = r.cursor;
+    skip_nested(r, 9)?;
+    let type_id = r.data[start];
+    let len = i32::from_be_bytes(r.data[start + 1..start + 5].try_into().unwrap()) as usize;
+    Ok(ListRef { type_id, len, data: &r.data[start + 5..r.cursor] })
+}
+
+fn read_compound<'a>(r: &mut NbtReader<'a>) -> Result<CompoundRef<'a>> {
+    let start = r.cursor;
+    skip_nested(r, 10)?;
+    Ok(CompoundRef { data: &r.data[start..r.cursor] })
+}
+
+enum Frame {
+    List(u8, usize),
+    Compound,
+}
+
+// Validates a list or compound body with an explicit stack rather than recursion, so
+// deeply nested input hits the depth limit before it can exhaust the thread's stack.
+fn skip_nested(r: &mut NbtReader<'_>, type_id: u8) -> Result<()> {
+    let mut stack = Vec::new();
+    let mut next = type_id;
+    loop {
+        match next {
+            9 => {
+                r.push_depth()?;
+                let element = r.read_u8()?;
+                let len = r.read_len()?;
+                r.account(4 * len as u64)?;
+                stack.push(Frame::List(element, len));
+            }
+            10 => {
+                r.push_depth()?;
+                stack.push(Frame::Compound);
+            }
+            _ => {
+                read_payload(r, next)?;
+            }
+        }
+        next = loop {
+            let Some(frame) = stack.last_mut() else {
+                return Ok(());
+            };
+            match frame {
+                Frame::List(element, remaining) if *remaining > 0 => {
+                    *remaining -= 1;
+                    r.account_tag(*element)?;
+                    break *element;
+                }
+                Frame::Compound => {
+                    let tag = r.read_u8()?;
+                    if tag != 0 {
+                        r.account_tag(tag)?;
+                        r.account(TAG_SIZES[8] + COMPOUND_ENTRY_SIZE)?;
+                        read_str(r)?;
+                        break tag;
+                    }
+                }
+                Frame::List(..) => {}
+            }
+            stack.pop();
+            r.pop_depth();
+        };
+    }
+}
+
+/// Reads a root compound, skipping its name if `named`.
+pub(crate) fn read_root<'a>(r: &mut NbtReader<'a>, named: bool) -> Result<ValueRef<'a>> {
+    match r.read_u8()? {
+        10 => {
+            r.account_tag(10)?;
+            if named {
+                read_str(r)?;
+            }
+            Ok(ValueRef::Compound(read_compound(r)?))
+        }
+        x => Err(Error::RootTagNotCompound(x)),
+    }
+}
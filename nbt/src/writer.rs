@@ -1,223 +1,177 @@
-use crate::{Nbt, NbtAfter764, NbtWriteTrait, Value, Result, Error};
+use std::io;
 
-pub struct NbtWriter {
-    pub data: Vec<u8>,
-}
+use crate::{Result, Error};
 
-impl NbtWriter {
-    pub fn new() -> NbtWriter {
-        NbtWriter {
-            data: Vec::new(),
-        }
+/// Appends `values` to `out` as big-endian ints, encoding into fixed-size chunks so that the loop
+/// has no per-element bounds checks.
+pub fn extend_be_i32s(out: &mut Vec<u8>, values: &[i32]) {
+    let start = out.len();
+    out.resize(start + values.len() * 4, 0);
+    for (chunk, v) in out[start..].as_chunks_mut::<4>().0.iter_mut().zip(values) {
+        *chunk = v.to_be_bytes();
     }
+}
 
-    #[inline]
-    pub fn write_u8(&mut self, value: u8) {
-        self.data.push(value);
+pub fn extend_be_i64s(out: &mut Vec<u8>, values: &[i64]) {
+    let start = out.len();
+    out.resize(start + values.len() * 8, 0);
+    for (chunk, v) in out[start..].as_chunks_mut::<8>().0.iter_mut().zip(values) {
+        *chunk = v.to_be_bytes();
     }
+}
+
+/// Converts the length of a list or array to the `i32` it is written as.
+pub(crate) fn checked_len(len: usize) -> Result<i32> {
+    i32::try_from(len).map_err(|_| Error::ArrayTooLong { len, max: i32::MAX as usize })
+}
+
+/// A destination for NBT bytes, shared by the in-memory and streaming writers.
+pub trait NbtSink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()>;
 
     #[inline]
-    pub fn write_i8(&mut self, value: i8) {
-        self.write_u8(value as u8);
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.write_bytes(&[value])
     }
 
     #[inline]
-    pub fn write_i16(&mut self, value: i16) {
-        self.data.extend_from_slice(&value.to_be_bytes());
+    fn write_i8(&mut self, value: i8) -> Result<()> {
+        self.write_u8(value as u8)
     }
 
     #[inline]
-    pub fn write_i32(&mut self, value: i32) {
-        self.data.extend_from_slice(&value.to_be_bytes());
+    fn write_i16(&mut self, value: i16) -> Result<()> {
+        self.write_bytes(&value.to_be_bytes())
     }
 
     #[inline]
-    pub fn write_i64(&mut self, value: i64) {
-        self.data.extend_from_slice(&value.to_be_bytes());
+    fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.write_bytes(&value.to_be_bytes())
     }
 
     #[inline]
-    pub fn write_f32(&mut self, value: f32) {
-        self.data.extend_from_slice(&value.to_be_bytes());
+    fn write_i32(&mut self, value: i32) -> Result<()> {
+        self.write_bytes(&value.to_be_bytes())
     }
 
     #[inline]
-    pub fn write_f64(&mut self, value: f64) {
-        self.data.extend_from_slice(&value.to_be_bytes());
+    fn write_i64(&mut self, value: i64) -> Result<()> {
+        self.write_bytes(&value.to_be_bytes())
     }
 
     #[inline]
-    pub fn write_string(&mut self, value: &str) {
-        self.write_i16(value.len() as i16);
-        self.data.extend_from_slice(value.as_bytes());
+    fn write_f32(&mut self, value: f32) -> Result<()> {
+        self.write_bytes(&value.to_be_bytes())
     }
-}
 
-impl NbtWriteTrait for Nbt {
     #[inline]
-    fn write_byte_array(w: &mut NbtWriter, value: &[i8]) {
-        w.write_i32(value.len() as i32);
-        for &v in value {
-            w.write_i8(v);
-        }
+    fn write_f64(&mut self, value: f64) -> Result<()> {
+        self.write_bytes(&value.to_be_bytes())
     }
 
+    /// Writes big-endian ints with a single `write_bytes`; see [`extend_be_i32s`].
     #[inline]
-    fn write_nbt_string(w: &mut NbtWriter, value: &str) {
-        w.write_string(value);
+    fn write_i32_array(&mut self, values: &[i32]) -> Result<()> {
+        let mut bytes = Vec::new();
+        extend_be_i32s(&mut bytes, values);
+        self.write_bytes(&bytes)
     }
 
     #[inline]
-    fn write_int_array(w: &mut NbtWriter, value: &[i32]) {
-        w.write_i32(value.len() as i32);
-        for &v in value {
-            w.write_i32(v);
-        }
+    fn write_i64_array(&mut self, values: &[i64]) -> Result<()> {
+        let mut bytes = Vec::new();
+        extend_be_i64s(&mut bytes, values);
+        self.write_bytes(&bytes)
     }
+}
 
-    #[inline]
-    fn write_long_array(w: &mut NbtWriter, value: &[i64]) {
-        w.write_i32(value.len() as i32);
-        for &v in value {
-            w.write_i64(v);
+#[derive(Default)]
+pub struct NbtWriter {
+    pub data: Vec<u8>,
+}
+
+impl NbtWriter {
+    pub fn new() -> NbtWriter {
+        NbtWriter {
+            data: Vec::new(),
         }
     }
+}
 
+impl NbtSink for NbtWriter {
     #[inline]
-    fn write_list(w: &mut NbtWriter, value: &[Value]) -> Result<()> {
-        if value.is_empty() {
-            w.write_i8(0);
-            w.write_i32(0);
-            return Ok(());
-        }
-        let tag = value.first().expect("empty list").tag();
-        if !value.iter().all(|v| v.tag() == tag) {
-            return Err(Error::ListTypeNotSame);
-        }
-        w.write_u8(tag);
-        w.write_i32(value.len() as i32);
-        for v in value {
-            match v {
-                Value::Byte(v) => w.write_i8(*v),
-                Value::Short(v) => w.write_i16(*v),
-                Value::Int(v) => w.write_i32(*v),
-                Value::Long(v) => w.write_i64(*v),
-                Value::Float(v) => w.write_f32(*v),
-                Value::Double(v) => w.write_f64(*v),
-                Value::ByteArray(v) => Self::write_byte_array(w, v),
-                Value::String(v) => Self::write_nbt_string(w, v),
-                Value::IntArray(v) => Self::write_int_array(w, v),
-                Value::LongArray(v) => Self::write_long_array(w, v),
-                Value::List(v) => Self::write_list(w, v)?,
-                Value::Compound(_, v) => Self::write_compound(w, None, v)?,
-            }
-        }
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.data.extend_from_slice(bytes);
         Ok(())
     }
 
     #[inline]
-    fn write_compound(w: &mut NbtWriter, name: Option<&String>, value: &[(String, Value)]) -> Result<()> {
-       if let Some(name) = name {
-           Self::write_nbt_string(w, &name);
-       }
-       for (name, value) in value {
-            w.write_u8(value.tag());
-            if let Value::Compound(_, _) = value {
-            } else {
-                Self::write_nbt_string(w, name);
-            }
-            match value {
-                Value::Byte(v) => w.write_i8(*v),
-                Value::Short(v) => w.write_i16(*v),
-                Value::Int(v) => w.write_i32(*v),
-                Value::Long(v) => w.write_i64(*v),
-                Value::Float(v) => w.write_f32(*v),
-                Value::Double(v) => w.write_f64(*v),
-                Value::ByteArray(v) => Self::write_byte_array(w, v),
-                Value::String(v) => Self::write_nbt_string(w, v),
-                Value::IntArray(v) => Self::write_int_array(w, v),
-                Value::LongArray(v) => Self::write_long_array(w, v),
-                Value::List(v) => Self::write_list(w, v)?,
-                Value::Compound(name, v) => Self::write_compound(w, name.as_ref(), v)?,
-            }
-        }
-        w.write_u8(0);
+    fn write_i32_array(&mut self, values: &[i32]) -> Result<()> {
+        extend_be_i32s(&mut self.data, values);
         Ok(())
     }
 
-    fn write_to(w: &mut NbtWriter, value: &Value) -> Result<()> {
-        match value {
-            Value::Compound(name, data) => {
-                w.write_u8(value.tag());
-                Self::write_compound(w, name.as_ref(), data)?;
-            }
-            x => return Err(Error::RootTagNotCompound(x.tag())),
-        }
+    #[inline]
+    fn write_i64_array(&mut self, values: &[i64]) -> Result<()> {
+        extend_be_i64s(&mut self.data, values);
         Ok(())
     }
+}
 
-    fn write_to_with_name(w: &mut self::NbtWriter, name: &str, value: &Value) -> Result<()> {
-        w.write_u8(value.tag());
-        Self::write_nbt_string(w, name);
-        Self::write_to(w, value)
-    }
+/// Writes NBT straight into any `io::Write`, such as a `GzEncoder`, a file or a socket.
+///
+/// Every primitive is a separate `write_all` call, so unbuffered sinks should be
+/// wrapped with [`NbtStreamWriter::buffered`].
+pub struct NbtStreamWriter<W> {
+    inner: W,
+    position: usize,
 }
 
-impl NbtWriteTrait for NbtAfter764 {
-    fn write_byte_array(w: &mut self::NbtWriter, value: &[i8]) {
-        Nbt::write_byte_array(w, value);
+impl<W: io::Write> NbtStreamWriter<W> {
+    pub fn new(inner: W) -> NbtStreamWriter<W> {
+        NbtStreamWriter {
+            inner,
+            position: 0,
+        }
     }
 
-    fn write_nbt_string(w: &mut self::NbtWriter, value: &str) {
-        Nbt::write_nbt_string(w, value);
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
     }
 
-    fn write_int_array(w: &mut self::NbtWriter, value: &[i32]) {
-        Nbt::write_int_array(w, value);
+    pub fn get_ref(&self) -> &W {
+        &self.inner
     }
 
-    fn write_long_array(w: &mut self::NbtWriter, value: &[i64]) {
-        Nbt::write_long_array(w, value);
+    /// Returns the sink without flushing it; a `BufWriter` would then lose any error from writing
+    /// out its buffer, so prefer [`finish`](NbtStreamWriter::finish).
+    pub fn into_inner(self) -> W {
+        self.inner
     }
 
-    fn write_list(w: &mut self::NbtWriter, value: &[Value]) -> Result<()> {
-        Nbt::write_list(w, value)
+    /// Flushes the sink and returns it.
+    pub fn finish(mut self) -> Result<W> {
+        self.inner.flush().map_err(Error::Io)?;
+        Ok(self.inner)
     }
+}
 
-    fn write_compound(w: &mut self::NbtWriter, name: Option<&String>, value: &[(String, Value)]) -> Result<()> {
-        Nbt::write_compound(w, name, value)
+impl<W: io::Write> NbtStreamWriter<io::BufWriter<W>> {
+    pub fn buffered(inner: W) -> NbtStreamWriter<io::BufWriter<W>> {
+        NbtStreamWriter::new(io::BufWriter::new(inner))
     }
+}
 
-    fn write_to(w: &mut self::NbtWriter, value: &Value) -> Result<()> {
-        match value {
-            Value::Compound(_, data) => {
-                w.write_u8(value.tag());
-                for (key, v) in data{
-                    w.write_u8(v.tag());
-                    Self::write_nbt_string(w, key);
-                    match v {
-                        Value::Byte(v) => w.write_i8(*v),
-                        Value::Short(v) => w.write_i16(*v),
-                        Value::Int(v) => w.write_i32(*v),
-                        Value::Long(v) => w.write_i64(*v),
-                        Value::Float(v) => w.write_f32(*v),
-                        Value::Double(v) => w.write_f64(*v),
-                        Value::ByteArray(v) => Self::write_byte_array(w, v),
-                        Value::String(v) => Self::write_nbt_string(w, v),
-                        Value::IntArray(v) => Self::write_int_array(w, v),
-                        Value::LongArray(v) => Self::write_long_array(w, v),
-                        Value::List(v) => Self::write_list(w, v)?,
-                        Value::Compound(name, v) => Self::write_compound(w, name.as_ref(), v)?,
-                    }
-                }
-                w.write_u8(0);
+impl<W: io::Write> NbtSink for NbtStreamWriter<W> {
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        match self.inner.write_all(bytes) {
+            Ok(()) => {
+                self.position += bytes.len();
                 Ok(())
             }
-            x => return Err(Error::RootTagNotCompound(x.tag())),
+            Err(e) => Err(Error::Io(e)),
         }
     }
-
-    fn write_to_with_name(w: &mut self::NbtWriter, _name: &str, value: &Value) -> Result<()> {
-        Nbt::write_to(w, value)
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use nbt::reader::NbtReader;
+use nbt::writer::NbtWriter;
+use nbt::{read_compressed, snbt, write_gzip, Compression, DialectKind, NamedTag, Nbt, Value};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nbt-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_nbt-cli")).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn playerdata(path: &Path) {
+    let player = snbt::from_str(
+        r#"{DataVersion:3700, Health:20.0f, Inventory:[
+            {Slot:0b, id:"minecraft:diamond_sword", Count:1b},
+            {Slot:1b, id:"minecraft:torch", Count:64b}
+        ]}"#,
+    )
+    .unwrap();
+    fs::write(path, write_gzip::<Nbt, _>(Vec::new(), &player).unwrap()).unwrap();
+}
+
+#[test]
+fn test_set_get_delete() {
+    let dir = temp_dir("edit");
+    let file = dir.join("player.dat");
+    playerdata(&file);
+    let file = file.to_str().unwrap();
+
+    assert_eq!(stdout(&cli(&["get", file, "Inventory[{Slot:1b}].Count"])), "64b\n");
+    stdout(&cli(&["set", file, "Inventory[{Slot:1b}].Count", "12b"]));
+    assert_eq!(stdout(&cli(&["get", file, "Inventory[{Slot:1b}].Count"])), "12b\n");
+
+    stdout(&cli(&["delete", file, "Inventory[{Slot:0b}]"]));
+    let missing = cli(&["get", file, "Inventory[{Slot:0b}]"]);
+    assert_eq!(missing.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&missing.stderr).contains("nothing matches"));
+
+    // the edited file is still gzip-compressed Java NBT
+    let data = fs::read(file).unwrap();
+    let (value, compression) = read_compressed::<Nbt, _>(&data[..]).unwrap();
+    assert_eq!(compression, Compression::Gzip);
+    assert_eq!(value.get("Inventory").unwrap().as_list().unwrap().len(), 1);
+    assert_eq!(value.get("Health"), Some(&Value::Float(20.0)));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_network_dialects() {
+    let dir = temp_dir("network");
+    let file = dir.join("player.dat");
+    playerdata(&file);
+    let file = file.to_str().unwrap();
+
+    for (dialect, kind) in [("java-network", DialectKind::NbtAfter764), ("bedrock-network", DialectKind::BedrockNetworkNbt)] {
+        let out = dir.join(format!("{}.nbt", dialect));
+        let out = out.to_str().unwrap();
+        stdout(&cli(&["convert", file, "--to", "nbt", "--dialect", dialect, "--compression", "none", "-o", out]));
+        let data = fs::read(out).unwrap();
+        let value = kind.read(&mut NbtReader::new(&data)).unwrap();
+        assert_eq!(value.get("DataVersion"), Some(&Value::Int(3700)));
+
+        // read back and edited in the same dialect
+        assert_eq!(stdout(&cli(&["get", out, "Inventory[0].id"])), "\"minecraft:diamond_sword\"\n");
+        stdout(&cli(&["set", out, "DataVersion", "3800"]));
+        let data = fs::read(out).unwrap();
+        assert_eq!(kind.read(&mut NbtReader::new(&data)).unwrap().get("DataVersion"), Some(&Value::Int(3800)));
+    }
+    assert_eq!(cli(&["convert", file, "--to", "nbt", "--dialect", "pocket"]).status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_binary_that_looks_like_text() {
+    let dir = temp_dir("text");
+    let file = dir.join("level.nbt");
+    let file = file.to_str().unwrap();
+    // root names of 123 and 91 bytes make uncompressed Bedrock files open with "\n{" and "\n["
+    for len in [b'{', b'['] {
+        let value = snbt::from_str("{Difficulty:2}").unwrap();
+        let mut w = NbtWriter::new();
+        DialectKind::BedrockNbt.write_named(&mut w, &NamedTag::new("n".repeat(len as usize), value)).unwrap();
+        assert_eq!(w.data[1], len);
+        fs::write(file, &w.data).unwrap();
+        assert_eq!(stdout(&cli(&["get", file, "Difficulty"])), "2\n");
+    }
+
+    // text that fails to parse, and isn't binary either, reports the text error
+    fs::write(file, "{Difficulty:").unwrap();
+    let output = cli(&["get", file, "Difficulty"]);
+    assert!(!output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("not a Java or Bedrock NBT file"));
+    fs::remove_dir_all(&dir).unwrap();
+}